@@ -1,4 +1,29 @@
-use orbit_api::{Key, ui::render::texture::TextureHandle};
+use std::path::PathBuf;
+
+use orbit_api::{Key, Topic, ui::render::texture::TextureHandle};
 
 /// The wallpaper texture for an output. Produced by `wallpaper`.
 pub const WALLPAPER_TEX: Key<TextureHandle> = Key::per_output("wallpaper/tex");
+
+/// The image file shown on an output. Produced by `wallpaper`.
+pub const WALLPAPER_PATH: Key<PathBuf> = Key::per_output("wallpaper/path");
+
+/// The dominant colors of the image shown on an output. Produced by
+/// `wallpaper`, for modules that theme themselves after it.
+pub const WALLPAPER_PALETTE: Key<Palette> = Key::per_output("wallpaper/palette");
+
+/// `true` while the session is locked, `false` once unlocked. Published by
+/// `lockscreen`.
+pub const SESSION_LOCKED: Topic<bool> = Topic::new("lockscreen/locked");
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Palette {
+    /// RGB colors, the one covering most of the image first.
+    pub colors: Vec<[u8; 3]>,
+}
+
+impl Palette {
+    pub fn dominant(&self) -> Option<[u8; 3]> {
+        self.colors.first().copied()
+    }
+}
@@ -1,7 +1,8 @@
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
 use orbit_api::{
-    Engine, Event, OrbitModule, Subscription, Task, orbit_config, orbit_plugin,
+    Broadcast, Engine, Event, OrbitCtl, OrbitModule, Subscription, Task, orbit_config,
+    orbit_plugin,
     ui::{
         el,
         graphics::TargetId,
@@ -10,6 +11,11 @@ use orbit_api::{
         widget::{Column, Element, Length, Row, Spacer, Text},
     },
 };
+use orbit_keys::{Palette, WALLPAPER_PALETTE};
+
+/// Brightest channel of a background taken from the wallpaper, so the
+/// white text stays readable.
+const WALLPAPER_BRIGHTNESS: f32 = 48.0;
 
 fn default_bar_height() -> u32 {
     32
@@ -17,6 +23,12 @@ fn default_bar_height() -> u32 {
 fn default_time_format() -> String {
     "%H:%M:%S".into()
 }
+fn default_position() -> String {
+    "top".into()
+}
+fn default_follow_wallpaper() -> bool {
+    true
+}
 
 #[orbit_config]
 pub struct Config {
@@ -24,6 +36,11 @@ pub struct Config {
     pub height: u32,
     #[serde(default = "default_time_format")]
     pub time_format: String,
+    #[serde(default = "default_position")]
+    pub position: String,
+    /// Color the background after the wallpaper's dominant color.
+    #[serde(default = "default_follow_wallpaper")]
+    pub follow_wallpaper: bool,
 }
 
 impl Default for Config {
@@ -31,10 +48,19 @@ impl Default for Config {
         Self {
             height: default_bar_height(),
             time_format: default_time_format(),
+            position: default_position(),
+            follow_wallpaper: default_follow_wallpaper(),
         }
     }
 }
 
+fn anchors_for_position(position: &str) -> Anchor {
+    match position {
+        "bottom" => Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT,
+        _ => Anchor::TOP | Anchor::LEFT | Anchor::RIGHT,
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum Msg {
     Tick,
@@ -44,6 +70,7 @@ pub enum Msg {
 pub struct Bar {
     now: chrono::DateTime<chrono::Local>,
     cfg: Config,
+    palette: HashMap<TargetId, Palette>,
 }
 
 impl Default for Bar {
@@ -51,10 +78,27 @@ impl Default for Bar {
         Self {
             now: chrono::Local::now(),
             cfg: Default::default(),
+            palette: HashMap::new(),
         }
     }
 }
 
+impl Bar {
+    fn background(&self, tid: &TargetId) -> Color {
+        let dominant = self
+            .palette
+            .get(tid)
+            .filter(|_| self.cfg.follow_wallpaper)
+            .and_then(Palette::dominant);
+        let Some(rgb) = dominant else {
+            return Color::BLACK;
+        };
+        let max = rgb.into_iter().max().unwrap_or(0).max(1) as f32;
+        let [r, g, b] = rgb.map(|c| (c as f32 / max * WALLPAPER_BRIGHTNESS) as u8);
+        Color::rgb(r, g, b)
+    }
+}
+
 impl OrbitModule for Bar {
     type Config = Config;
     type Message = Msg;
@@ -64,6 +108,8 @@ impl OrbitModule for Bar {
     fn validate_config(cfg: Self::Config) -> Result<(), String> {
         if cfg.height < 1 {
             Err("Height must be at least 1".into())
+        } else if !matches!(cfg.position.as_str(), "top" | "bottom") {
+            Err("position must be one of: top, bottom".into())
         } else {
             Ok(())
         }
@@ -80,9 +126,11 @@ impl OrbitModule for Bar {
             return false;
         };
 
-        if layer.size.height != self.cfg.height {
+        let anchors = anchors_for_position(&self.cfg.position);
+        if layer.size.height != self.cfg.height || layer.anchors != anchors {
             layer.size.height = self.cfg.height;
             layer.exclusive_zone = self.cfg.height as i32;
+            layer.anchors = anchors;
             true
         } else {
             false
@@ -91,11 +139,18 @@ impl OrbitModule for Bar {
 
     fn update<'a>(
         &mut self,
-        _ctl: &mut orbit_api::OrbitCtl,
-        _tid: Option<TargetId>,
+        ctl: &mut orbit_api::OrbitCtl,
+        tid: Option<TargetId>,
         _engine: &mut Engine<'a>,
         event: &Event<Self::Message>,
     ) -> Task<Msg> {
+        if let Some(tid) = tid
+            && !self.palette.contains_key(&tid)
+            && let Some(palette) = ctl.get(WALLPAPER_PALETTE)
+        {
+            self.palette.insert(tid, palette);
+        }
+
         if let Event::Message(Msg::Tick) = event {
             self.now = chrono::Local::now();
             Task::RedrawModule
@@ -104,7 +159,26 @@ impl OrbitModule for Bar {
         }
     }
 
-    fn view(&self, _tid: &TargetId, _theme: &orbit_api::ui::theme::Theme) -> Element {
+    fn on_broadcast(
+        &mut self,
+        ctl: &mut OrbitCtl<'_>,
+        tid: Option<TargetId>,
+        broadcast: &Broadcast,
+    ) -> Task<Self::Message> {
+        let Some(tid) = tid else {
+            return Task::None;
+        };
+        if broadcast.key != WALLPAPER_PALETTE.id {
+            return Task::None;
+        }
+        match ctl.get(WALLPAPER_PALETTE) {
+            Some(palette) => self.palette.insert(tid, palette),
+            None => self.palette.remove(&tid),
+        };
+        Task::RedrawTarget
+    }
+
+    fn view(&self, tid: &TargetId, _theme: &orbit_api::ui::theme::Theme) -> Element {
         Row::new(el![
             Spacer::new(Size::splat(Length::Grow)),
             Column::new(el![
@@ -115,7 +189,7 @@ impl OrbitModule for Bar {
             .size(Size::new(Length::Fit, Length::Grow)),
             Spacer::new(Size::splat(Length::Grow)),
         ])
-        .color(Color::BLACK)
+        .color(self.background(tid))
         .size(Size::splat(Length::Grow))
         .into()
     }
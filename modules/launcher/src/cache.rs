@@ -0,0 +1,158 @@
+//! Parsed desktop files and decoded icons, persisted under
+//! `$XDG_CACHE_HOME/orbit/launcher` so a rescan only redoes what changed.
+
+use std::{
+    collections::HashMap,
+    fs,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use orbit_api::{
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+
+use crate::desktop::{DesktopFile, Env};
+
+fn cache_dir() -> Option<PathBuf> {
+    let cache = match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+    };
+    Some(cache.join("orbit/launcher"))
+}
+
+/// Modification time in nanoseconds since the epoch, 0 if unknown.
+pub fn mtime(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos() as u64)
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "orbit_api::serde")]
+struct CachedFile {
+    mtime: u64,
+    parsed: DesktopFile,
+}
+
+/// Desktop files by path, as parsed when they had `mtime`.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "orbit_api::serde")]
+pub struct Entries {
+    /// What the files were localized and filtered for. The whole cache is
+    /// dropped when it changes.
+    env: Env,
+    files: HashMap<PathBuf, CachedFile>,
+    #[serde(skip)]
+    changed: bool,
+}
+
+impl Entries {
+    fn path() -> Option<PathBuf> {
+        Some(cache_dir()?.join("entries.json"))
+    }
+
+    /// The cache for `env`, empty if there is none or it was made for
+    /// another locale or desktop.
+    pub fn load(env: Env) -> Self {
+        let cached = Self::path()
+            .and_then(|p| fs::read(p).ok())
+            .and_then(|data| serde_json::from_slice::<Self>(&data).ok())
+            .filter(|cached| cached.env == env);
+        match cached {
+            Some(cached) => cached,
+            None => Self {
+                env,
+                files: HashMap::new(),
+                changed: true,
+            },
+        }
+    }
+
+    pub fn env(&self) -> &Env {
+        &self.env
+    }
+
+    /// Takes the cached parse of `path` out if it's still current.
+    pub fn take(&mut self, path: &Path, mtime: u64) -> Option<DesktopFile> {
+        match self.files.remove(path) {
+            Some(cached) if cached.mtime == mtime => Some(cached.parsed),
+            _ => {
+                self.changed = true;
+                None
+            }
+        }
+    }
+
+    /// A cache of what was scanned this time. Files not taken out are gone.
+    pub fn rebuild(mut self, scanned: HashMap<PathBuf, (u64, DesktopFile)>) -> Self {
+        self.changed |= !self.files.is_empty();
+        self.files = scanned
+            .into_iter()
+            .map(|(path, (mtime, parsed))| (path, CachedFile { mtime, parsed }))
+            .collect();
+        self
+    }
+
+    /// Writes the cache back if anything was re-parsed or removed.
+    pub fn save(&self) {
+        if !self.changed {
+            return;
+        }
+        let Some(path) = Self::path() else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        if let Ok(data) = serde_json::to_vec(self) {
+            let _ = fs::write(path, data);
+        }
+    }
+}
+
+/// Where the `px` square RGBA pixels of icon `name` from `theme` are kept.
+fn icon_path(theme: &str, px: u32, name: &str) -> Option<PathBuf> {
+    let file = name.replace('/', "%");
+    Some(
+        cache_dir()?
+            .join("icons")
+            .join(theme)
+            .join(px.to_string())
+            .join(file),
+    )
+}
+
+/// What cached icon pixels start with: the file they were decoded from and
+/// its modification time, as `mtime<TAB>path<LF>`.
+fn icon_header(source: &Path, mtime: u64) -> Vec<u8> {
+    let mut header = format!("{mtime}\t").into_bytes();
+    header.extend_from_slice(source.as_os_str().as_bytes());
+    header.push(b'\n');
+    header
+}
+
+/// Cached pixels of an icon, if they were decoded from `source` when it had
+/// `mtime`. A lookup resolving to another file, or a changed file, misses.
+pub fn icon(theme: &str, px: u32, name: &str, source: &Path, mtime: u64) -> Option<Vec<u8>> {
+    let data = fs::read(icon_path(theme, px, name)?).ok()?;
+    let rgba = data.strip_prefix(icon_header(source, mtime).as_slice())?;
+    (rgba.len() == (px * px * 4) as usize).then(|| rgba.to_vec())
+}
+
+pub fn store_icon(theme: &str, px: u32, name: &str, source: &Path, mtime: u64, rgba: &[u8]) {
+    let Some(path) = icon_path(theme, px, name) else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let mut data = icon_header(source, mtime);
+    data.extend_from_slice(rgba);
+    let _ = fs::write(path, data);
+}
@@ -30,6 +30,47 @@ pub struct Config {
     pub position: String,
     #[serde(default)]
     pub launch_options: String,
+    /// Command `Terminal=true` apps are run with, like `foot` or
+    /// `alacritty -e`. Defaults to `$TERMINAL -e`, then `xterm -e`.
+    #[serde(default)]
+    pub terminal: Option<String>,
+    /// Icon theme to look icons up in, falling back to what it inherits and
+    /// `hicolor`. Defaults to the GTK icon theme.
+    #[serde(default)]
+    pub icon_theme: Option<String>,
+    /// External commands listing results for their own prefix, checked
+    /// before the built-in ones.
+    #[serde(default)]
+    pub providers: Vec<ScriptProvider>,
+}
+
+/// A command run on every query starting with `prefix`, printing results as
+/// JSON lines like `{"title": .., "subtitle": .., "icon": .., "exec": ..}`.
+#[orbit_config]
+pub struct ScriptProvider {
+    pub prefix: String,
+    /// Run with `sh -c`.
+    pub command: String,
+    #[serde(default)]
+    pub input: ScriptInput,
+    /// Shown when the command lists nothing.
+    #[serde(default)]
+    pub hint: Option<String>,
+    /// What the search bar's placeholder calls it. Defaults to the name of
+    /// the command's program, without extension.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// How a provider command gets the query.
+#[orbit_config]
+#[derive(Copy, Default, PartialEq)]
+pub enum ScriptInput {
+    /// As its first argument, `$1`.
+    #[default]
+    Arg,
+    /// As a line on stdin.
+    Stdin,
 }
 
 impl Default for Config {
@@ -41,6 +82,9 @@ impl Default for Config {
             icon_size: default_icon_size(),
             position: default_position(),
             launch_options: "".to_owned(),
+            terminal: None,
+            icon_theme: None,
+            providers: Vec::new(),
         }
     }
 }
@@ -0,0 +1,392 @@
+//! Parsing of `.desktop` files, after the freedesktop Desktop Entry spec.
+
+use std::{
+    collections::HashMap,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use orbit_api::serde::{Deserialize, Serialize};
+
+use crate::{RawEntry, helpers::parse_exec_args};
+
+/// What entries are filtered and localized against, read once per scan.
+#[derive(PartialEq, Serialize, Deserialize)]
+#[serde(crate = "orbit_api::serde")]
+pub struct Env {
+    /// Locale keys to look for, best first: `lang_COUNTRY@MODIFIER`,
+    /// `lang_COUNTRY`, `lang@MODIFIER`, `lang`.
+    locales: Vec<String>,
+    /// `$XDG_CURRENT_DESKTOP`, for `OnlyShowIn` and `NotShowIn`.
+    desktops: Vec<String>,
+}
+
+impl Env {
+    pub fn from_env() -> Self {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|v| !v.is_empty())
+            .unwrap_or_default();
+        let desktops = std::env::var("XDG_CURRENT_DESKTOP")
+            .unwrap_or_default()
+            .split(':')
+            .filter(|d| !d.is_empty())
+            .map(str::to_owned)
+            .collect();
+        Self {
+            locales: locale_variants(&locale),
+            desktops,
+        }
+    }
+}
+
+fn locale_variants(locale: &str) -> Vec<String> {
+    let (rest, modifier) = match locale.split_once('@') {
+        Some((rest, modifier)) => (rest, Some(modifier)),
+        None => (locale, None),
+    };
+    let rest = rest.split_once('.').map_or(rest, |(rest, _encoding)| rest);
+    let (lang, country) = match rest.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (rest, None),
+    };
+    if lang.is_empty() || lang == "C" || lang == "POSIX" {
+        return Vec::new();
+    }
+
+    let mut out = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        out.push(format!("{lang}_{country}@{modifier}"));
+    }
+    if let Some(country) = country {
+        out.push(format!("{lang}_{country}"));
+    }
+    if let Some(modifier) = modifier {
+        out.push(format!("{lang}@{modifier}"));
+    }
+    out.push(lang.to_owned());
+    out
+}
+
+/// A localestring value, keeping the best match for the locale seen so far.
+#[derive(Default)]
+struct Localized {
+    value: Option<String>,
+    /// Index into `Env::locales`, or its length for the unlocalized key.
+    rank: usize,
+}
+
+impl Localized {
+    fn offer(&mut self, rank: usize, value: &str) {
+        if self.value.is_none() || rank < self.rank {
+            self.value = Some(value.to_owned());
+            self.rank = rank;
+        }
+    }
+
+    fn get(self) -> String {
+        self.value.unwrap_or_default()
+    }
+}
+
+/// Which group of the file lines belong to.
+enum Group {
+    Entry,
+    Action(String),
+    Other,
+}
+
+/// A `[Desktop Action <id>]` group.
+#[derive(Default)]
+struct ActionGroup {
+    name: Localized,
+    exec: String,
+    icon: String,
+}
+
+/// What a desktop file parsed to: the app followed by its desktop actions,
+/// or nothing if it isn't an application or shouldn't be shown here.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(crate = "orbit_api::serde")]
+pub struct DesktopFile {
+    entries: Vec<RawEntry>,
+    /// Checked on every scan rather than when parsing, since it depends on
+    /// what's installed, not on the file.
+    try_exec: Option<String>,
+}
+
+impl DesktopFile {
+    pub fn entries(&self) -> &[RawEntry] {
+        match &self.try_exec {
+            Some(program) if !is_executable(program) => &[],
+            _ => &self.entries,
+        }
+    }
+}
+
+pub fn parse(text: &str, id: String, file: &Path, env: &Env) -> DesktopFile {
+    parse_app(text, id, file, env).unwrap_or_default()
+}
+
+fn parse_app(text: &str, id: String, file: &Path, env: &Env) -> Option<DesktopFile> {
+    let mut group = Group::Other;
+    let mut name = Localized::default();
+    let mut generic_name = Localized::default();
+    let mut comment = Localized::default();
+    let mut keywords = Localized::default();
+    let mut exec = String::new();
+    let mut icon = String::new();
+    let mut path = None;
+    let mut try_exec = None;
+    let mut only_show_in = None;
+    let mut not_show_in = Vec::new();
+    let mut terminal = false;
+    let mut dbus = false;
+    let mut no_display = false;
+    let mut hidden = false;
+    let mut is_app = false;
+    let mut action_ids = Vec::new();
+    let mut action_groups: HashMap<String, ActionGroup> = HashMap::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            group = if header == "Desktop Entry" {
+                Group::Entry
+            } else if let Some(action) = header.strip_prefix("Desktop Action ") {
+                Group::Action(action.to_owned())
+            } else {
+                Group::Other
+            };
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some((key, val)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, val) = (key.trim(), val.trim());
+
+        // `Key[locale]`, ranked by how well the locale matches.
+        let (key, rank) = match key.strip_suffix(']').and_then(|k| k.split_once('[')) {
+            Some((key, locale)) => match env.locales.iter().position(|l| l == locale) {
+                Some(rank) => (key, rank),
+                None => continue,
+            },
+            None => (key, env.locales.len()),
+        };
+        let is_true = || val.eq_ignore_ascii_case("true");
+
+        if let Group::Action(action) = &group {
+            let action = action_groups.entry(action.clone()).or_default();
+            match key {
+                "Name" => action.name.offer(rank, &unescape(val)),
+                "Exec" => action.exec = unescape(val),
+                "Icon" => action.icon = unescape(val),
+                _ => {}
+            }
+            continue;
+        }
+        if !matches!(group, Group::Entry) {
+            continue;
+        }
+
+        match key {
+            "Type" => is_app = val == "Application",
+            "Name" => name.offer(rank, &unescape(val)),
+            "GenericName" => generic_name.offer(rank, &unescape(val)),
+            "Comment" => comment.offer(rank, &unescape(val)),
+            "Keywords" => keywords.offer(rank, val),
+            "Exec" => exec = unescape(val),
+            "Icon" => icon = unescape(val),
+            "Path" if !val.is_empty() => path = Some(PathBuf::from(unescape(val))),
+            "TryExec" if !val.is_empty() => try_exec = Some(unescape(val)),
+            "OnlyShowIn" => only_show_in = Some(list(val)),
+            "NotShowIn" => not_show_in = list(val),
+            "Terminal" => terminal = is_true(),
+            "DBusActivatable" => dbus = is_true(),
+            "NoDisplay" => no_display = is_true(),
+            "Hidden" => hidden = is_true(),
+            "Actions" => action_ids = list(val),
+            _ => {}
+        }
+    }
+
+    let name = name.get();
+    if !is_app || no_display || hidden || name.is_empty() || (exec.is_empty() && !dbus) {
+        return None;
+    }
+    if let Some(only) = only_show_in
+        && !only.iter().any(|d| env.desktops.contains(d))
+    {
+        return None;
+    }
+    if not_show_in.iter().any(|d| env.desktops.contains(d)) {
+        return None;
+    }
+
+    let comment = comment.get();
+    let app = RawEntry {
+        id,
+        action: None,
+        name,
+        description: if comment.is_empty() {
+            generic_name.get()
+        } else {
+            comment
+        },
+        exec,
+        icon_name: icon,
+        keywords: list(&keywords.get())
+            .into_iter()
+            .map(|k| k.to_lowercase())
+            .collect(),
+        terminal,
+        path,
+        dbus,
+        file: file.to_path_buf(),
+    };
+
+    // Only the actions the entry lists, in its order.
+    let actions: Vec<RawEntry> = action_ids
+        .into_iter()
+        .filter_map(|action| {
+            let ActionGroup { name, exec, icon } = action_groups.remove(&action)?;
+            let name = name.get();
+            if name.is_empty() || (exec.is_empty() && !app.dbus) {
+                return None;
+            }
+            Some(RawEntry {
+                action: Some(action),
+                name: format!("{}: {name}", app.name),
+                exec,
+                icon_name: if icon.is_empty() {
+                    app.icon_name.clone()
+                } else {
+                    icon
+                },
+                ..app.clone()
+            })
+        })
+        .collect();
+    Some(DesktopFile {
+        entries: std::iter::once(app).chain(actions).collect(),
+        try_exec,
+    })
+}
+
+/// Undoes the `\s`, `\n`, `\t`, `\r` and `\\` escapes of string values.
+fn unescape(val: &str) -> String {
+    let mut out = String::with_capacity(val.len());
+    let mut chars = val.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => out.push(' '),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// A `;` separated list, where `\;` is a literal semicolon.
+fn list(val: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut chars = val.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(';') => current.push(';'),
+                Some(c) => {
+                    current.push('\\');
+                    current.push(c);
+                }
+                None => current.push('\\'),
+            },
+            ';' => out.push(unescape(&std::mem::take(&mut current))),
+            _ => current.push(c),
+        }
+    }
+    out.push(unescape(&current));
+    out.retain(|s| !s.is_empty());
+    out
+}
+
+/// Whether `program` is an executable path, or found as one on `$PATH`.
+fn is_executable(program: &str) -> bool {
+    let executable = |path: &Path| {
+        path.metadata()
+            .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+    };
+    if program.contains('/') {
+        return executable(Path::new(program));
+    }
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|dir| executable(&dir.join(program)))
+    })
+}
+
+/// The arguments `entry` is run with, its field codes expanded. The
+/// launcher never passes files or URLs, so `%f`, `%u` and friends go away.
+/// `None` if `Exec` has no program.
+pub fn exec_args(entry: &RawEntry) -> Option<Vec<String>> {
+    let mut split = Vec::new();
+    if !parse_exec_args(&entry.exec, &mut split) {
+        return None;
+    }
+
+    let mut args = Vec::with_capacity(split.len());
+    for arg in split {
+        match arg.as_str() {
+            "%f" | "%F" | "%u" | "%U" | "%d" | "%D" | "%n" | "%N" | "%v" | "%m" => continue,
+            "%i" => {
+                if !entry.icon_name.is_empty() {
+                    args.push("--icon".to_owned());
+                    args.push(entry.icon_name.clone());
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        let mut out = String::with_capacity(arg.len());
+        let mut chars = arg.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('%') => out.push('%'),
+                Some('c') => out.push_str(&entry.name),
+                Some('k') => out.push_str(&entry.file.to_string_lossy()),
+                // The rest expand to nothing here, or aren't valid codes.
+                _ => {}
+            }
+        }
+        args.push(out);
+    }
+    (!args.is_empty()).then_some(args)
+}
+
+/// D-Bus well-known name and object path of a `DBusActivatable` entry,
+/// both derived from its desktop file ID.
+pub fn dbus_name(entry: &RawEntry) -> (String, String) {
+    let name = entry
+        .id
+        .strip_suffix(".desktop")
+        .unwrap_or(&entry.id)
+        .to_owned();
+    let path = format!("/{}", name.replace('.', "/").replace('-', "_"));
+    (name, path)
+}
@@ -0,0 +1,146 @@
+/// Points per matched character.
+const MATCH: i32 = 16;
+/// Extra for a match right after the previous one.
+const CONSECUTIVE: i32 = 12;
+/// Extra for matching the first character of the text.
+const FIRST: i32 = 24;
+/// Extra for matching the start of a word.
+const WORD_START: i32 = 16;
+/// Largest penalty for one gap between matches.
+const MAX_GAP: i32 = 8;
+
+/// Scores `query` as a subsequence of `text`, ignoring case and whitespace
+/// in the query. Returns the score and the char indices of `text` that
+/// matched, or `None` if not every query character was found in order.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i32, Vec<usize>)> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let original: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = original
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let first = *query.first()?;
+
+    // Greedy from every place the first character occurs, keeping the best.
+    let mut best: Option<(i32, Vec<usize>)> = None;
+    'starts: for start in (0..lower.len()).filter(|&i| lower[i] == first) {
+        let mut positions = vec![start];
+        let mut at = start + 1;
+        for &c in &query[1..] {
+            // Later starts have even less text left, so none of them match.
+            let Some(found) = lower[at..].iter().position(|&t| t == c) else {
+                break 'starts;
+            };
+            positions.push(at + found);
+            at += found + 1;
+        }
+        let score = score(&original, &positions);
+        if best.as_ref().is_none_or(|(b, _)| score > *b) {
+            best = Some((score, positions));
+        }
+    }
+    best
+}
+
+/// [`fuzzy_match`] against `primary` and `others`, keeping the best score.
+/// Matches in `others` only count half, and have nothing to highlight, so
+/// the positions are always `primary`'s.
+pub fn fuzzy_match_fields<'a>(
+    query: &str,
+    primary: &str,
+    others: impl IntoIterator<Item = &'a str>,
+) -> Option<(i32, Vec<usize>)> {
+    let other = others
+        .into_iter()
+        .filter_map(|text| fuzzy_match(query, text))
+        .map(|(score, _)| score / 2)
+        .max();
+    match (fuzzy_match(query, primary), other) {
+        (Some((score, positions)), Some(other)) => Some((score.max(other), positions)),
+        (Some(primary), None) => Some(primary),
+        (None, Some(other)) => Some((other, Vec::new())),
+        (None, None) => None,
+    }
+}
+
+fn score(text: &[char], positions: &[usize]) -> i32 {
+    let mut score = 0;
+    let mut prev: Option<usize> = None;
+    for &p in positions {
+        score += MATCH;
+        if p == 0 {
+            score += FIRST;
+        } else if is_word_start(text, p) {
+            score += WORD_START;
+        }
+        match prev {
+            Some(prev) if p == prev + 1 => score += CONSECUTIVE,
+            Some(prev) => score -= ((p - prev - 1) as i32).min(MAX_GAP),
+            // Matches further into the text count a little less.
+            None => score -= (p as i32).min(MAX_GAP),
+        }
+        prev = Some(p);
+    }
+    score
+}
+
+fn is_word_start(text: &[char], i: usize) -> bool {
+    let (before, c) = (text[i - 1], text[i]);
+    !before.is_alphanumeric() || (before.is_lowercase() && c.is_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fuzzy_match, fuzzy_match_fields};
+
+    fn positions(query: &str, text: &str) -> Option<Vec<usize>> {
+        fuzzy_match(query, text).map(|(_, positions)| positions)
+    }
+
+    #[test]
+    fn matches_a_subsequence() {
+        assert_eq!(positions("ab", "a_xa_b"), Some(vec![0, 5]));
+        assert_eq!(positions("ff", "Firefox"), Some(vec![0, 4]));
+    }
+
+    #[test]
+    fn keeps_a_match_when_a_later_start_has_none() {
+        assert_eq!(positions("ab", "ab_a"), Some(vec![0, 1]));
+        assert_eq!(positions("ab", "xa_b_a"), Some(vec![1, 3]));
+    }
+
+    #[test]
+    fn prefers_word_starts() {
+        assert_eq!(positions("fb", "foo fbar bar"), Some(vec![4, 5]));
+    }
+
+    #[test]
+    fn ignores_case_and_whitespace_in_the_query() {
+        assert_eq!(positions("F F", "firefox"), Some(vec![0, 4]));
+    }
+
+    #[test]
+    fn other_fields_count_half_without_positions() {
+        let (full, _) = fuzzy_match("web", "web").unwrap();
+        assert_eq!(
+            fuzzy_match_fields("web", "Firefox", ["web browser"]),
+            Some((full / 2, Vec::new()))
+        );
+        assert_eq!(
+            fuzzy_match_fields("web", "Web", ["web"]),
+            fuzzy_match("web", "Web")
+        );
+        assert_eq!(fuzzy_match_fields("web", "Firefox", ["browser"]), None);
+    }
+
+    #[test]
+    fn rejects_what_is_out_of_order_or_missing() {
+        assert_eq!(positions("ba", "ab"), None);
+        assert_eq!(positions("abc", "ab"), None);
+        assert_eq!(positions("", "ab"), None);
+    }
+}
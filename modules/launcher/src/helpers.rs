@@ -1,8 +1,17 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use image::imageops::FilterType;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use resvg::{tiny_skia, usvg};
+use zbus::zvariant::Value;
 
-use crate::{Msg, RawEntry};
+use crate::{
+    Match, Msg, RawEntry, cache, desktop, fuzzy::fuzzy_match_fields, history::History, icons::Icons,
+};
 
 fn xdg_data_dirs() -> Vec<PathBuf> {
     let mut dirs = Vec::new();
@@ -22,238 +31,252 @@ fn xdg_data_dirs() -> Vec<PathBuf> {
     dirs
 }
 
-pub async fn scan_desktop_files() -> Msg {
-    let mut seen = std::collections::HashSet::new();
+pub fn scan_desktop_files() -> Msg {
+    let mut cache = cache::Entries::load(desktop::Env::from_env());
+    let mut scanned = HashMap::new();
+    let mut seen = HashSet::new();
     let mut out = Vec::new();
 
     for dir in xdg_data_dirs() {
-        let Ok(rd) = std::fs::read_dir(&dir) else {
-            continue;
-        };
-        for entry in rd.flatten() {
-            let path = entry.path();
-            if path.extension().and_then(|e| e.to_str()) != Some("desktop") {
-                continue;
-            }
-            let Ok(text) = std::fs::read_to_string(&path) else {
+        let mut files = Vec::new();
+        collect_desktop_files(&dir, &mut files);
+        for path in files {
+            let Ok(rel) = path.strip_prefix(&dir) else {
                 continue;
             };
-            if let Some(raw) = parse_desktop(&text)
-                && seen.insert(raw.name.clone())
-            {
-                out.push(raw);
+            // Subdirectories become part of the ID, so
+            // `kde/okular.desktop` is `kde-okular.desktop`.
+            let id = rel
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("-");
+            // Earlier directories take precedence, even to hide an entry.
+            if !seen.insert(id.clone()) {
+                continue;
             }
+            let mtime = cache::mtime(&path);
+            let parsed = match cache.take(&path, mtime) {
+                Some(parsed) => parsed,
+                None => {
+                    let Ok(text) = std::fs::read_to_string(&path) else {
+                        continue;
+                    };
+                    desktop::parse(&text, id, &path, cache.env())
+                }
+            };
+            out.extend(parsed.entries().iter().cloned());
+            scanned.insert(path, (mtime, parsed));
         }
     }
+    cache.rebuild(scanned).save();
 
     out.sort_by(|a, b| a.name.cmp(&b.name));
     Msg::ScannedApps(Arc::new(out))
 }
 
-fn parse_desktop(text: &str) -> Option<RawEntry> {
-    let mut in_desktop_entry = false;
-    let mut name = String::new();
-    let mut description = String::new();
-    let mut exec = String::new();
-    let mut icon = String::new();
-    let mut keywords: Vec<String> = Vec::new();
-    let mut no_display = false;
-    let mut hidden = false;
-    let mut is_app = false;
-
-    for line in text.lines() {
-        let line = line.trim();
-        if line.starts_with('[') {
-            in_desktop_entry = line == "[Desktop Entry]";
-            continue;
-        }
-        if !in_desktop_entry || line.starts_with('#') {
-            continue;
-        }
-        let Some((key, val)) = line.split_once('=') else {
-            continue;
+/// Calls `on_change` whenever a desktop file is added, changed or removed,
+/// until the returned watcher is dropped. Directories that don't exist yet
+/// aren't watched.
+pub fn watch_desktop_files<F>(on_change: F) -> Option<RecommendedWatcher>
+where
+    F: Fn() + Send + 'static,
+{
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(ev) = res else {
+            return;
         };
-        match key.trim() {
-            "Type" => is_app = val.trim() == "Application",
-            "Name" => {
-                if name.is_empty() {
-                    name = val.trim().to_owned();
-                }
-            }
-            "Comment" => {
-                if description.is_empty() {
-                    description = val.trim().to_owned();
-                }
-            }
-            "GenericName" => {
-                if description.is_empty() {
-                    description = val.trim().to_owned();
-                }
-            }
-            "Exec" => exec = strip_field_codes(val.trim()),
-            "Icon" => icon = val.trim().to_owned(),
-            "Keywords" => {
-                keywords = val
-                    .trim()
-                    .split(';')
-                    .filter(|s| !s.is_empty())
-                    .map(|s| s.to_lowercase())
-                    .collect();
-            }
-            "NoDisplay" => no_display = val.trim().eq_ignore_ascii_case("true"),
-            "Hidden" => hidden = val.trim().eq_ignore_ascii_case("true"),
-            _ => {}
+        // Scanning only opens and reads, which doesn't count.
+        if matches!(
+            ev.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+        ) {
+            on_change();
         }
-    }
-
-    if !is_app || no_display || hidden || name.is_empty() || exec.is_empty() {
-        return None;
-    }
-
-    Some(RawEntry {
-        name,
-        description,
-        exec,
-        icon_name: icon,
-        keywords,
     })
+    .ok()?;
+    for dir in xdg_data_dirs() {
+        let _ = watcher.watch(&dir, RecursiveMode::Recursive);
+    }
+    Some(watcher)
 }
 
-fn strip_field_codes(exec: &str) -> String {
-    let mut out = String::with_capacity(exec.len());
-    let mut chars = exec.chars().peekable();
-    while let Some(c) = chars.next() {
-        if c == '%' {
-            match chars.peek() {
-                Some('%') => {
-                    out.push('%');
-                    chars.next();
-                }
-                Some(_) => {
-                    chars.next();
-                }
-                None => {}
-            }
-        } else {
-            out.push(c);
+fn collect_desktop_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(rd) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in rd.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_desktop_files(&path, out);
+        } else if path.extension().and_then(|e| e.to_str()) == Some("desktop") {
+            out.push(path);
         }
     }
-    out.trim().to_owned()
 }
 
-fn icon_search_paths(icon_name: &str, size: u32) -> Vec<PathBuf> {
-    if icon_name.starts_with('/') {
-        return vec![PathBuf::from(icon_name)];
+/// Finds `icon_name` in `icons` for `size` logical pixels and decodes it
+/// to `px` physical ones, rasterizing SVGs at that size. Decoded icons are
+/// cached on disk.
+fn decode_icon(icons: &Icons, icon_name: &str, size: u32, px: u32) -> Option<Vec<u8>> {
+    let scale = px.div_ceil(size.max(1)).max(1);
+    let path = icons.lookup(icon_name, size, scale)?;
+    // Taken before decoding, so a change made meanwhile misses next time.
+    let mtime = cache::mtime(&path);
+    if let Some(rgba) = cache::icon(icons.theme(), px, icon_name, &path, mtime) {
+        return Some(rgba);
     }
+    let rgba = if path.extension().and_then(|e| e.to_str()) == Some("svg") {
+        render_svg(&path, px)?
+    } else {
+        let img = image::ImageReader::open(&path).ok()?.decode().ok()?;
+        let img = img.resize_to_fill(px, px, FilterType::Lanczos3);
+        img.to_rgba8().into_raw()
+    };
+    cache::store_icon(icons.theme(), px, icon_name, &path, mtime, &rgba);
+    Some(rgba)
+}
 
-    let size_str = format!("{}x{}", size, size);
-
-    let mut bases: Vec<PathBuf> = Vec::new();
-    if let Some(h) = std::env::var_os("XDG_DATA_HOME") {
-        bases.push(PathBuf::from(h));
-    } else if let Some(h) = std::env::var_os("HOME") {
-        bases.push(PathBuf::from(h).join(".local/share"));
-    }
-    let sys =
-        std::env::var("XDG_DATA_DIRS").unwrap_or_else(|_| "/usr/local/share:/usr/share".into());
-    for p in sys.split(':') {
-        bases.push(PathBuf::from(p));
+/// The icon of the app entry at `index`, see [`decode_icon`].
+pub fn load_icon(index: usize, icons: &Icons, icon_name: &str, size: u32, px: u32) -> Msg {
+    match decode_icon(icons, icon_name, size, px) {
+        Some(rgba) => Msg::IconLoaded(index, px, px, Arc::new(rgba)),
+        None => Msg::IconLoaded(index, 0, 0, Arc::new(Vec::new())),
     }
+}
 
-    let subdirs = [
-        format!("icons/hicolor/{}/apps", size_str),
-        "icons/hicolor/scalable/apps".to_owned(),
-        "icons/hicolor/48x48/apps".to_owned(),
-        "icons/hicolor/256x256/apps".to_owned(),
-        "icons/Adwaita/32x32/apps".to_owned(),
-        "pixmaps".to_owned(),
-    ];
-
-    let mut paths = Vec::new();
-    for base in &bases {
-        for sub in &subdirs {
-            let dir = base.join(sub);
-            for ext in &["png", "xpm"] {
-                paths.push(dir.join(format!("{}.{}", icon_name, ext)));
-            }
-        }
+/// A provider item's icon, loaded once per name.
+pub fn load_item_icon(icons: &Icons, icon_name: String, size: u32, px: u32) -> Msg {
+    match decode_icon(icons, &icon_name, size, px) {
+        Some(rgba) => Msg::ItemIconLoaded(icon_name, px, px, Arc::new(rgba)),
+        None => Msg::ItemIconLoaded(icon_name, 0, 0, Arc::new(Vec::new())),
     }
-    paths
 }
 
-pub async fn load_icon(index: usize, icon_name: &str, size: u32) -> Msg {
-    for path in icon_search_paths(icon_name, size) {
-        if !path.exists() {
-            continue;
-        }
-        if path.extension().and_then(|e| e.to_str()) == Some("svg") {
-            continue;
-        }
-        let Ok(reader) = image::ImageReader::open(&path) else {
-            continue;
-        };
-        let Ok(img) = reader.decode() else {
-            continue;
-        };
-        let img = img.resize_to_fill(size, size, FilterType::Lanczos3);
-        let rgba = img.to_rgba8();
-        return Msg::IconLoaded(index, size, size, Arc::new(rgba.into_raw()));
-    }
-    Msg::IconLoaded(index, 0, 0, Arc::new(Vec::new()))
+/// Rasterizes the SVG at `path` into a `px` square, keeping its aspect.
+fn render_svg(path: &Path, px: u32) -> Option<Vec<u8>> {
+    let data = std::fs::read(path).ok()?;
+    let tree = usvg::Tree::from_data(&data, &usvg::Options::default()).ok()?;
+    let (w, h) = (tree.size().width(), tree.size().height());
+    let scale = px as f32 / w.max(h);
+    let offset = |side: f32| (px as f32 - side * scale) / 2.0;
+    let transform =
+        tiny_skia::Transform::from_scale(scale, scale).post_translate(offset(w), offset(h));
+
+    let mut pixmap = tiny_skia::Pixmap::new(px, px)?;
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+    Some(
+        pixmap
+            .pixels()
+            .iter()
+            .flat_map(|p| {
+                let c = p.demultiply();
+                [c.red(), c.green(), c.blue(), c.alpha()]
+            })
+            .collect(),
+    )
 }
 
-pub fn search(apps: &[RawEntry], query: &str, max: usize) -> Msg {
-    let q = query.to_lowercase();
+/// How much launch history counts against match quality. A frecency of
+/// 10 is worth about two well-placed matched characters.
+const FRECENCY_WEIGHT: f32 = 16.0;
 
-    let mut scored: Vec<(usize, u8)> = apps
+pub fn search(apps: &[RawEntry], history: &History, query: &str, max: usize) -> Msg {
+    let mut scored: Vec<(f32, Match)> = apps
         .iter()
         .enumerate()
-        .filter_map(|(i, app)| {
-            let name_lower = app.name.to_lowercase();
-            if name_lower.starts_with(&q) {
-                Some((i, 3u8))
-            } else if name_lower.contains(&q) {
-                Some((i, 2u8))
-            } else if app.description.to_lowercase().contains(&q)
-                || app.keywords.iter().any(|k| k.contains(&q))
-            {
-                Some((i, 1u8))
-            } else {
-                None
-            }
+        .filter_map(|(index, app)| {
+            let others = std::iter::once(app.description.as_str())
+                .chain(app.keywords.iter().map(String::as_str));
+            let (score, positions) = fuzzy_match_fields(query, &app.name, others)?;
+            let frecency = (1.0 + history.frecency(&app.key())).ln() * FRECENCY_WEIGHT;
+            Some((score as f32 + frecency, Match { index, positions }))
         })
         .collect();
 
-    scored.sort_by(|a, b| b.1.cmp(&a.1).then(apps[a.0].name.cmp(&apps[b.0].name)));
+    scored.sort_by(|a, b| {
+        b.0.total_cmp(&a.0)
+            .then_with(|| apps[a.1.index].name.cmp(&apps[b.1.index].name))
+    });
     scored.truncate(max);
 
-    Msg::Results(scored.into_iter().map(|(i, _)| i).collect())
+    Msg::Results(scored.into_iter().map(|(_, m)| m).collect())
+}
+
+/// Runs `app`, through D-Bus activation if it supports it and otherwise its
+/// `Exec`, in `terminal` if it asks for one and wrapped in `cmd`.
+pub async fn launch_app(app: RawEntry, cmd: Vec<String>, mut terminal: Vec<String>) -> Msg {
+    if app.dbus && activate(&app).await.is_ok() {
+        return Msg::Launched;
+    }
+    let Some(args) = desktop::exec_args(&app) else {
+        return Msg::Launched;
+    };
+    if !app.terminal {
+        terminal.clear();
+    }
+    terminal.extend(args);
+    spawn(terminal, cmd, app.path.as_deref()).await
 }
 
-pub async fn launch_app(exec: String, mut cmd: Vec<String>) -> Msg {
+/// Runs `args` wrapped in `cmd`, the launch options, in `dir` if given.
+pub async fn spawn(args: Vec<String>, mut cmd: Vec<String>, dir: Option<&Path>) -> Msg {
     let post = match cmd.iter().position(|c| c == "%command%") {
         Some(idx) => cmd.drain(idx..).skip(1).collect::<Vec<_>>(),
         None => Vec::new(),
     };
-
-    if !parse_exec_args(&exec, &mut cmd) {
-        return Msg::Launched;
-    }
-
+    cmd.extend(args);
     cmd.extend(post);
 
     if let Some(program) = cmd.first() {
-        let _ = async_process::Command::new(program)
+        let mut command = async_process::Command::new(program);
+        command
             .args(&cmd[1..])
             .stdin(async_process::Stdio::null())
             .stdout(async_process::Stdio::null())
-            .stderr(async_process::Stdio::null())
-            .spawn();
+            .stderr(async_process::Stdio::null());
+        if let Some(dir) = dir.filter(|dir| dir.is_dir()) {
+            command.current_dir(dir);
+        }
+        let _ = command.spawn();
     }
 
     Msg::Launched
 }
 
+/// Calls `org.freedesktop.Application.Activate`, or `ActivateAction` for
+/// desktop actions, on the app's bus name.
+async fn activate(app: &RawEntry) -> zbus::Result<()> {
+    let (name, path) = desktop::dbus_name(app);
+    let conn = zbus::Connection::session().await?;
+    let platform_data: HashMap<&str, Value<'_>> = HashMap::new();
+    let iface = Some("org.freedesktop.Application");
+    match &app.action {
+        Some(action) => {
+            let parameter: Vec<Value<'_>> = Vec::new();
+            conn.call_method(
+                Some(name.as_str()),
+                path.as_str(),
+                iface,
+                "ActivateAction",
+                &(action.as_str(), parameter, platform_data),
+            )
+            .await?
+        }
+        None => {
+            conn.call_method(
+                Some(name.as_str()),
+                path.as_str(),
+                iface,
+                "Activate",
+                &(platform_data,),
+            )
+            .await?
+        }
+    };
+    Ok(())
+}
+
 pub fn parse_exec_args(exec: &str, args: &mut Vec<String>) -> bool {
     let mut has_exec = false;
     let mut current = String::new();
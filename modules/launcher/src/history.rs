@@ -0,0 +1,117 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const DAY: u64 = 24 * 60 * 60;
+
+#[derive(Clone, Copy, Debug)]
+struct Launches {
+    count: u32,
+    /// Unix time of the latest launch.
+    last: u64,
+}
+
+/// How often and how recently each entry was launched, persisted across
+/// sessions in `$XDG_STATE_HOME/orbit/launcher/history`.
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    launches: HashMap<String, Launches>,
+}
+
+fn history_path() -> Option<PathBuf> {
+    let state = match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(state.join("orbit/launcher/history"))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+impl History {
+    /// Empty if there is no history yet or it can't be read.
+    pub fn load() -> Self {
+        let Some(text) = history_path().and_then(|p| fs::read_to_string(p).ok()) else {
+            return Self::default();
+        };
+        // One `count<TAB>last<TAB>key` line per entry.
+        let launches = text
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let count = fields.next()?.parse().ok()?;
+                let last = fields.next()?.parse().ok()?;
+                Some((fields.next()?.to_owned(), Launches { count, last }))
+            })
+            .collect();
+        Self { launches }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = history_path() else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let mut text = String::new();
+        for (key, l) in &self.launches {
+            let _ = writeln!(text, "{}\t{}\t{}", l.count, l.last, key);
+        }
+        // Renamed into place, so a crash mid-write can't leave half a file.
+        let tmp = path.with_extension("tmp");
+        if fs::write(&tmp, text).is_ok() {
+            let _ = fs::rename(tmp, path);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.launches.is_empty()
+    }
+
+    /// Adds the launches recorded in `newer` on top of these.
+    pub fn merge(&mut self, newer: &History) {
+        for (key, l) in &newer.launches {
+            let entry = self
+                .launches
+                .entry(key.clone())
+                .or_insert(Launches { count: 0, last: 0 });
+            entry.count += l.count;
+            entry.last = entry.last.max(l.last);
+        }
+    }
+
+    pub fn record(&mut self, key: &str) {
+        let entry = self
+            .launches
+            .entry(key.to_owned())
+            .or_insert(Launches { count: 0, last: 0 });
+        entry.count += 1;
+        entry.last = now();
+    }
+
+    /// Launch count weighted by how recent the last launch was; 0 for
+    /// entries never launched.
+    pub fn frecency(&self, key: &str) -> f32 {
+        let Some(l) = self.launches.get(key) else {
+            return 0.0;
+        };
+        let age = now().saturating_sub(l.last);
+        let weight = match age {
+            a if a < 4 * DAY => 1.0,
+            a if a < 14 * DAY => 0.7,
+            a if a < 31 * DAY => 0.5,
+            a if a < 90 * DAY => 0.3,
+            _ => 0.1,
+        };
+        l.count as f32 * weight
+    }
+}
@@ -0,0 +1,268 @@
+//! Icon lookup after the freedesktop Icon Theme spec.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Formats looked for, in order of preference. XPM is left out since it
+/// can't be decoded.
+const EXTENSIONS: [&str; 2] = ["png", "svg"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DirKind {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+/// One directory of a theme, like `48x48/apps`, with the icons it holds
+/// across all base directories.
+#[derive(Debug)]
+struct ThemeDir {
+    size: u32,
+    scale: u32,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+    kind: DirKind,
+    icons: HashMap<String, PathBuf>,
+}
+
+impl ThemeDir {
+    fn matches(&self, size: u32, scale: u32) -> bool {
+        if self.scale != scale {
+            return false;
+        }
+        match self.kind {
+            DirKind::Fixed => self.size == size,
+            DirKind::Scalable => (self.min_size..=self.max_size).contains(&size),
+            DirKind::Threshold => (self.size.saturating_sub(self.threshold)
+                ..=self.size + self.threshold)
+                .contains(&size),
+        }
+    }
+
+    /// How far off this directory is in pixels, 0 if it covers `size`.
+    fn distance(&self, size: u32, scale: u32) -> u32 {
+        let px = size * scale;
+        let (min, max) = match self.kind {
+            DirKind::Fixed => (self.size, self.size),
+            DirKind::Scalable => (self.min_size, self.max_size),
+            DirKind::Threshold => (
+                self.size.saturating_sub(self.threshold),
+                self.size + self.threshold,
+            ),
+        };
+        let (min, max) = (min * self.scale, max * self.scale);
+        if px < min {
+            min - px
+        } else {
+            px.saturating_sub(max)
+        }
+    }
+}
+
+/// Every icon of the configured theme, the themes it inherits and
+/// `hicolor`, indexed up front so lookups don't touch the disk.
+#[derive(Debug, Default)]
+pub struct Icons {
+    /// The theme looked in first.
+    theme: String,
+    /// One entry per theme, in lookup order.
+    themes: Vec<Vec<ThemeDir>>,
+    /// Icons outside any theme, like those in `/usr/share/pixmaps`.
+    fallback: HashMap<String, PathBuf>,
+}
+
+/// `$HOME/.icons`, `$XDG_DATA_DIRS/icons` and `/usr/share/pixmaps`, in the
+/// order the spec searches them.
+fn base_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(home) = std::env::var_os("HOME") {
+        dirs.push(PathBuf::from(&home).join(".icons"));
+    }
+    if let Some(data) = std::env::var_os("XDG_DATA_HOME") {
+        dirs.push(PathBuf::from(data).join("icons"));
+    } else if let Some(home) = std::env::var_os("HOME") {
+        dirs.push(PathBuf::from(home).join(".local/share/icons"));
+    }
+    let sys =
+        std::env::var("XDG_DATA_DIRS").unwrap_or_else(|_| "/usr/local/share:/usr/share".into());
+    for p in sys.split(':') {
+        dirs.push(PathBuf::from(p).join("icons"));
+    }
+    dirs.push(PathBuf::from("/usr/share/pixmaps"));
+    dirs
+}
+
+/// The GTK icon theme, when none is configured.
+fn desktop_theme() -> Option<String> {
+    let config = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    ["gtk-4.0", "gtk-3.0"].iter().find_map(|gtk| {
+        let text = fs::read_to_string(config.join(gtk).join("settings.ini")).ok()?;
+        let groups = parse_ini(&text);
+        let name = groups.get("Settings")?.get("gtk-icon-theme-name")?;
+        Some(name.trim_matches('"').to_owned())
+    })
+}
+
+/// `[Group]` -> key -> value.
+fn parse_ini(text: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut groups: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut current = String::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(group) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = group.to_owned();
+        } else if let Some((key, val)) = line.split_once('=')
+            && !line.starts_with('#')
+        {
+            groups
+                .entry(current.clone())
+                .or_default()
+                .insert(key.trim().to_owned(), val.trim().to_owned());
+        }
+    }
+    groups
+}
+
+fn list(val: Option<&String>) -> Vec<String> {
+    val.map(|v| {
+        v.split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_owned)
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Adds the icons in `dir` to `icons`, keeping any already found.
+fn index_dir(dir: &Path, icons: &mut HashMap<String, PathBuf>) {
+    let Ok(rd) = fs::read_dir(dir) else {
+        return;
+    };
+    let files: Vec<PathBuf> = rd.flatten().map(|e| e.path()).collect();
+    for ext in EXTENSIONS {
+        for path in &files {
+            if path.extension().and_then(|e| e.to_str()) != Some(ext) {
+                continue;
+            }
+            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                icons.entry(stem.to_owned()).or_insert_with(|| path.clone());
+            }
+        }
+    }
+}
+
+impl Icons {
+    /// Indexes `theme`, or the desktop's if `None`, with what it inherits.
+    pub fn load(theme: Option<&str>) -> Self {
+        let bases = base_dirs();
+        let mut icons = Self::default();
+        let mut seen = HashSet::new();
+
+        let mut pending: Vec<String> = theme
+            .map(str::to_owned)
+            .or_else(desktop_theme)
+            .into_iter()
+            .collect();
+        icons.theme = pending.first().cloned().unwrap_or_else(|| "hicolor".into());
+        // Depth first through `Inherits`, with `hicolor` always last.
+        while let Some(name) = pending
+            .pop()
+            .or_else(|| (!seen.contains("hicolor")).then(|| "hicolor".to_owned()))
+        {
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            let Some(text) = bases
+                .iter()
+                .find_map(|b| fs::read_to_string(b.join(&name).join("index.theme")).ok())
+            else {
+                continue;
+            };
+            let groups = parse_ini(&text);
+            let Some(meta) = groups.get("Icon Theme") else {
+                continue;
+            };
+
+            let mut dirs = Vec::new();
+            let subdirs = list(meta.get("Directories"))
+                .into_iter()
+                .chain(list(meta.get("ScaledDirectories")));
+            for subdir in subdirs {
+                let Some(group) = groups.get(&subdir) else {
+                    continue;
+                };
+                let num = |key: &str| group.get(key).and_then(|v| v.parse::<u32>().ok());
+                let Some(size) = num("Size") else {
+                    continue;
+                };
+                let kind = match group.get("Type").map(String::as_str) {
+                    Some("Fixed") => DirKind::Fixed,
+                    Some("Scalable") => DirKind::Scalable,
+                    _ => DirKind::Threshold,
+                };
+                let mut dir = ThemeDir {
+                    size,
+                    scale: num("Scale").unwrap_or(1),
+                    min_size: num("MinSize").unwrap_or(size),
+                    max_size: num("MaxSize").unwrap_or(size),
+                    threshold: num("Threshold").unwrap_or(2),
+                    kind,
+                    icons: HashMap::new(),
+                };
+                for base in &bases {
+                    index_dir(&base.join(&name).join(&subdir), &mut dir.icons);
+                }
+                if !dir.icons.is_empty() {
+                    dirs.push(dir);
+                }
+            }
+            icons.themes.push(dirs);
+
+            let parents = list(meta.get("Inherits"));
+            pending.extend(parents.into_iter().rev());
+        }
+
+        for base in &bases {
+            index_dir(base, &mut icons.fallback);
+        }
+        icons
+    }
+
+    pub fn theme(&self) -> &str {
+        &self.theme
+    }
+
+    /// The file for `name` at `size` logical pixels and integer `scale`.
+    /// Each theme is searched for an exact size match, then the closest
+    /// size, before moving on to the next.
+    pub fn lookup(&self, name: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        if name.starts_with('/') {
+            return Some(PathBuf::from(name));
+        }
+        for dirs in &self.themes {
+            let exact = dirs
+                .iter()
+                .filter(|d| d.matches(size, scale))
+                .find_map(|d| d.icons.get(name));
+            let found = exact.or_else(|| {
+                dirs.iter()
+                    .filter_map(|d| Some((d.distance(size, scale), d.icons.get(name)?)))
+                    .min_by_key(|(distance, _)| *distance)
+                    .map(|(_, path)| path)
+            });
+            if let Some(path) = found {
+                return Some(path.clone());
+            }
+        }
+        self.fallback.get(name).cloned()
+    }
+}
@@ -1,7 +1,11 @@
-use std::sync::Arc;
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
+
+use async_io::Timer;
+use futures_lite::future;
 
 use orbit_api::{
-    Engine, Event, OrbitModule, Task, orbit_plugin,
+    CommandArgs, Engine, Event, OrbitModule, Subscription, Task, orbit_plugin,
+    serde::{Deserialize, Serialize},
     ui::{
         el,
         event::{KeyEvent, LogicalKey},
@@ -17,34 +21,90 @@ use orbit_api::{
     },
 };
 
-use crate::config::Config;
+use crate::{
+    config::Config,
+    history::History,
+    icons::Icons,
+    providers::{Action, Dmenu, Item, Provider, Script},
+};
+mod cache;
 mod config;
+mod desktop;
+mod fuzzy;
 mod helpers;
+mod history;
+mod icons;
+mod providers;
 
-#[derive(Debug, Clone)]
+/// How long a burst of desktop file changes gets to settle before a rescan.
+const SETTLE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "orbit_api::serde")]
 pub struct RawEntry {
+    /// Desktop file ID, like `org.gnome.Nautilus.desktop`.
+    pub id: String,
+    /// Set for a desktop action of the app `id`, which is named like
+    /// `Firefox: New Private Window`.
+    pub action: Option<String>,
     pub name: String,
     pub description: String,
+    /// `Exec` with string escapes undone, field codes still in.
     pub exec: String,
     pub icon_name: String,
     pub keywords: Vec<String>,
+    pub terminal: bool,
+    /// Working directory to launch in.
+    pub path: Option<PathBuf>,
+    pub dbus: bool,
+    /// The desktop file itself, for `%k`.
+    pub file: PathBuf,
+}
+
+impl RawEntry {
+    /// What launches are remembered under: the ID, plus the action if any.
+    pub fn key(&self) -> String {
+        match &self.action {
+            Some(action) => format!("{}#{action}", self.id),
+            None => self.id.clone(),
+        }
+    }
+}
+
+/// One search result.
+#[derive(Debug, Clone)]
+pub struct Match {
+    /// Index into `Launcher::entries`.
+    pub index: usize,
+    /// Char indices of the name the query matched, for highlighting.
+    pub positions: Vec<usize>,
 }
 
 #[derive(Debug, Clone)]
 struct AppEntry {
+    key: String,
     name: String,
     description: String,
-    exec: String,
     icon_name: String,
     icon: Option<TextureHandle>,
+    has_actions: bool,
 }
 
 #[derive(Clone, Debug)]
 pub enum Msg {
     ScannedApps(Arc<Vec<RawEntry>>),
-    Results(Vec<usize>),
+    Results(Vec<Match>),
+    HistoryLoaded(Arc<History>),
+    HistorySaved,
+    IconsIndexed(Arc<Icons>),
+    /// A provider's items, for the query with this number.
+    Items(u64, Vec<Item>),
     IconLoaded(usize, u32, u32, Arc<Vec<u8>>),
+    ItemIconLoaded(String, u32, u32, Arc<Vec<u8>>),
     Refresh,
+    /// Desktop files changed on disk, maybe while hidden.
+    AppsChanged,
+    Dmenu(CommandArgs),
     Launched,
 }
 
@@ -56,32 +116,88 @@ pub struct Launcher {
     query: String,
     apps: Arc<Vec<RawEntry>>,
     entries: Vec<AppEntry>,
-    /// Indices into `entries` for the current result set.
-    /// When query is empty this is all entries in order.
-    results: Vec<usize>,
+    /// The current result set. When query is empty this is all entries,
+    /// most frecent first.
+    results: Vec<Match>,
+    /// What a provider listed, in place of `results` while its prefix is
+    /// typed.
+    items: Option<Vec<Item>>,
+    /// Numbers the provider queries, so only the latest one's items count.
+    query_id: u64,
+    /// Dropped to stop the provider query still running.
+    running_query: Option<async_channel::Sender<()>>,
+    /// Providers from the config, ahead of the built-in ones.
+    scripts: Vec<Arc<dyn Provider>>,
+    /// Set while picking for `orbit command launcher dmenu`, which takes
+    /// the whole query.
+    dmenu: Option<Arc<Dmenu>>,
+    /// Icons of provider items by name, `None` while loading or if missing.
+    item_icons: HashMap<String, Option<TextureHandle>>,
+    history: Arc<History>,
+    /// Set once the saved history is in. Until then `history` only holds
+    /// launches since, which must not overwrite the file.
+    history_loaded: bool,
+    /// Index of the app whose actions are listed in place of the results.
+    expanded: Option<usize>,
     selected: usize,
     atlas: Option<Atlas>,
+    icons: Option<Arc<Icons>>,
+    /// Scale of the output the launcher was last drawn on.
+    scale: f32,
 }
 
 impl Launcher {
-    fn active_exec(&self) -> Option<&str> {
+    /// The selected app, as scanned.
+    fn active_app(&self) -> Option<&RawEntry> {
         self.results
             .get(self.selected)
-            .and_then(|&i| self.entries.get(i))
-            .map(|e| e.exec.as_str())
+            .and_then(|m| self.apps.get(m.index))
     }
 
-    fn clamp_selection(&mut self) {
-        if self.results.is_empty() {
-            self.selected = 0;
-        } else {
-            self.selected = self.selected.min(self.results.len() - 1);
+    /// What `Terminal=true` apps are run in: the configured command, else
+    /// `$TERMINAL -e`, else `xterm -e`.
+    fn terminal(&self) -> Vec<String> {
+        let mut terminal = Vec::new();
+        match &self.cfg.terminal {
+            Some(command) => {
+                helpers::parse_exec_args(command, &mut terminal);
+            }
+            None => {
+                let program = std::env::var("TERMINAL")
+                    .ok()
+                    .filter(|t| !t.is_empty())
+                    .unwrap_or_else(|| "xterm".to_owned());
+                terminal.extend([program, "-e".to_owned()]);
+            }
         }
+        terminal
+    }
+
+    /// The provider the query is for, with the query it gets. `None` for
+    /// apps.
+    fn provider(&self) -> Option<(Arc<dyn Provider>, &str)> {
+        if let Some(dmenu) = &self.dmenu {
+            return Some((Arc::clone(dmenu) as Arc<dyn Provider>, &self.query));
+        }
+        providers::find(&self.scripts, &self.query)
+            .or_else(|| providers::find(providers::builtin(), &self.query))
+    }
+
+    /// How many rows are listed.
+    fn shown(&self) -> usize {
+        match &self.items {
+            Some(items) => items.len(),
+            None => self.results.len(),
+        }
+    }
+
+    fn clamp_selection(&mut self) {
+        self.selected = self.selected.min(self.shown().saturating_sub(1));
     }
 
     fn select_next(&mut self, step: usize) {
-        if !self.results.is_empty() {
-            self.selected = (self.selected + step).min(self.results.len() - 1);
+        if self.shown() > 0 {
+            self.selected = (self.selected + step).min(self.shown() - 1);
         }
     }
 
@@ -90,34 +206,249 @@ impl Launcher {
     }
 
     fn show_all(&mut self) {
-        self.results = (0..self.entries.len()).collect();
+        // Actions only show up when searched for or expanded.
+        let mut all: Vec<usize> = (0..self.apps.len())
+            .filter(|&i| self.apps[i].action.is_none())
+            .collect();
+        // Stable, so entries never launched stay sorted by name.
+        all.sort_by(|&a, &b| {
+            let frecency = |i: usize| self.history.frecency(&self.entries[i].key);
+            frecency(b).total_cmp(&frecency(a))
+        });
+        self.results = all
+            .into_iter()
+            .map(|index| Match {
+                index,
+                positions: Vec::new(),
+            })
+            .collect();
+        self.expanded = None;
         self.selected = 0;
     }
 
+    /// Lists the selected app followed by its actions, with the first
+    /// action selected. `false` if it has none.
+    fn expand(&mut self) -> bool {
+        if self.items.is_some() {
+            return false;
+        }
+        let Some(index) = self.results.get(self.selected).map(|m| m.index) else {
+            return false;
+        };
+        let app = &self.apps[index];
+        if app.action.is_some() {
+            return false;
+        }
+        let actions: Vec<usize> = (0..self.apps.len())
+            .filter(|&i| self.apps[i].id == app.id && self.apps[i].action.is_some())
+            .collect();
+        if actions.is_empty() {
+            return false;
+        }
+        self.results = std::iter::once(index)
+            .chain(actions)
+            .map(|index| Match {
+                index,
+                positions: Vec::new(),
+            })
+            .collect();
+        self.expanded = Some(index);
+        self.selected = 1;
+        true
+    }
+
+    /// Back from an expanded app to the results it was picked from.
+    fn collapse(&mut self) -> Task<Msg> {
+        let Some(app) = self.expanded.take() else {
+            return Task::None;
+        };
+        if self.query.is_empty() {
+            self.show_all();
+            self.selected = self
+                .results
+                .iter()
+                .position(|m| m.index == app)
+                .unwrap_or(0);
+            Task::RedrawTarget
+        } else {
+            self.selected = 0;
+            self.search()
+        }
+    }
+
+    /// Lists what the query asks for after it changed: a provider's items,
+    /// every app, or the apps it matches.
+    fn requery(&mut self) -> Task<Msg> {
+        self.expanded = None;
+        self.selected = 0;
+        self.running_query = None;
+        if let Some((provider, query)) = self.provider() {
+            let items = provider.items(query.to_owned(), self.cfg.max_results);
+            // The last items stay up until the new ones are in.
+            self.items.get_or_insert_with(Vec::new);
+            self.query_id += 1;
+            let id = self.query_id;
+            let (stop, stopped) = async_channel::bounded::<()>(1);
+            self.running_query = Some(stop);
+            return Task::spawn(async move {
+                let stopped = async {
+                    let _ = stopped.recv().await;
+                    Vec::new()
+                };
+                Msg::Items(id, future::or(items, stopped).await)
+            });
+        }
+        self.items = None;
+        if self.query.is_empty() {
+            self.show_all();
+            Task::RedrawTarget
+        } else {
+            self.search()
+        }
+    }
+
+    /// Carries out the selected provider item.
+    fn activate_item(&mut self) -> Task<Msg> {
+        let Some(item) = self
+            .items
+            .as_ref()
+            .and_then(|items| items.get(self.selected))
+        else {
+            // Like dmenu, picking when nothing matches answers what's typed.
+            if self.dmenu.is_some() && !self.query.is_empty() {
+                let query = self.query.clone();
+                return self.close(Some(query));
+            }
+            return Task::None;
+        };
+        match item.action.clone() {
+            Action::Query(query) => {
+                self.query = query;
+                self.requery()
+            }
+            Action::Spawn(args) => {
+                self.query.clear();
+                self.items = None;
+                self.show_all();
+                let cmd = self.launch_options.clone();
+                Task::spawn(async move { helpers::spawn(args, cmd, None).await })
+            }
+            Action::Reply(line) => self.close(Some(line)),
+            Action::Activate(window) => {
+                window.run();
+                self.close(None)
+            }
+        }
+    }
+
+    /// Clears the query and closes, answering the `dmenu` caller if there
+    /// is one.
+    fn close(&mut self, answer: Option<String>) -> Task<Msg> {
+        if let Some(dmenu) = self.dmenu.take() {
+            dmenu.reply(answer);
+        }
+        self.reset();
+        Task::ExitModule
+    }
+
+    /// Back to listing every app, for the next time the launcher shows.
+    fn reset(&mut self) {
+        self.query.clear();
+        self.items = None;
+        self.running_query = None;
+        self.show_all();
+    }
+
+    fn search(&self) -> Task<Msg> {
+        let query = self.query.clone();
+        let apps = Arc::clone(&self.apps);
+        let history = Arc::clone(&self.history);
+        let max = self.cfg.max_results;
+        Task::blocking(move || helpers::search(&apps, &history, &query, max))
+    }
+
     fn rebuild_entries_from_apps(&mut self) {
         use std::collections::HashMap;
         let old: HashMap<String, Option<TextureHandle>> = self
             .entries
             .drain(..)
-            .map(|e| (e.name.clone(), e.icon))
+            .map(|e| (e.key.clone(), e.icon))
             .collect();
 
         self.entries = self
             .apps
             .iter()
             .map(|raw| AppEntry {
+                key: raw.key(),
                 name: raw.name.clone(),
                 description: raw.description.clone(),
-                exec: raw.exec.clone(),
                 icon_name: raw.icon_name.clone(),
-                icon: old.get(&raw.name).and_then(|h| *h),
+                icon: old.get(&raw.key()).and_then(|h| *h),
+                has_actions: raw.action.is_none()
+                    && self
+                        .apps
+                        .iter()
+                        .any(|a| a.id == raw.id && a.action.is_some()),
             })
             .collect();
     }
 
+    /// Loads the icons not loaded yet, once both the apps and the icon
+    /// theme are in.
+    fn load_icons(&self) -> Task<Msg> {
+        let Some(icons) = &self.icons else {
+            return Task::None;
+        };
+        let (size, px) = (self.cfg.icon_size, self.icon_px());
+        let tasks: Vec<Task<Msg>> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.icon.is_none() && !e.icon_name.is_empty())
+            .map(|(i, e)| {
+                let icons = Arc::clone(icons);
+                let icon_name = e.icon_name.clone();
+                Task::blocking(move || helpers::load_icon(i, &icons, &icon_name, size, px))
+            })
+            .collect();
+        Task::batch(tasks)
+    }
+
+    /// Loads the icons of the listed provider items not loaded yet.
+    fn load_item_icons(&mut self) -> Task<Msg> {
+        let (Some(icons), Some(items)) = (&self.icons, &self.items) else {
+            return Task::None;
+        };
+        let (size, px) = (self.cfg.icon_size, self.icon_px());
+        let mut tasks = Vec::new();
+        for name in items.iter().filter_map(|item| item.icon.as_ref()) {
+            if self.item_icons.contains_key(name) {
+                continue;
+            }
+            self.item_icons.insert(name.clone(), None);
+            let (icons, name) = (Arc::clone(icons), name.clone());
+            tasks.push(Task::blocking(move || {
+                helpers::load_item_icon(&icons, name, size, px)
+            }));
+        }
+        Task::batch(tasks)
+    }
+
+    /// Side of an icon texture on the current output.
+    fn icon_px(&self) -> u32 {
+        ((self.cfg.icon_size as f32 * self.scale).round() as u32).max(1)
+    }
+
+    fn index_icons(&self) -> Task<Msg> {
+        let theme = self.cfg.icon_theme.clone();
+        Task::blocking(move || Msg::IconsIndexed(Arc::new(Icons::load(theme.as_deref()))))
+    }
+
     fn ensure_atlas(&mut self, engine: &mut Engine<'_>) {
         if self.atlas.is_none() {
-            self.atlas = Some(engine.create_atlas(512, 512, AllocatorKind::Skyline));
+            // Room for as many icons on HiDPI outputs.
+            let side = (512.0 * self.scale.max(1.0)).ceil() as u32;
+            self.atlas = Some(engine.create_atlas(side, side, AllocatorKind::Skyline));
         }
     }
 
@@ -128,6 +459,7 @@ impl Launcher {
         for entry in &mut self.entries {
             entry.icon = None;
         }
+        self.item_icons.clear();
     }
 }
 
@@ -138,10 +470,15 @@ impl OrbitModule for Launcher {
     fn cleanup<'a>(&mut self, engine: &mut Engine<'a>) {
         self.query.clear();
         self.results.clear();
+        self.items = None;
         self.selected = 0;
         self.destroy_atlas(engine);
         self.entries.clear();
         self.apps = Arc::new(Vec::new());
+        self.icons = None;
+        if let Some(dmenu) = self.dmenu.take() {
+            dmenu.reply(None);
+        }
     }
 
     fn validate_config(cfg: Self::Config) -> Result<(), String> {
@@ -160,20 +497,49 @@ impl OrbitModule for Launcher {
         if !matches!(cfg.position.as_str(), "top" | "center" | "bottom") {
             return Err("position must be one of: top, center, bottom".into());
         }
+        if let Some(terminal) = &cfg.terminal
+            && !helpers::parse_exec_args(terminal, &mut Vec::new())
+        {
+            return Err("terminal must name a program".into());
+        }
+        for provider in &cfg.providers {
+            if provider.prefix.trim().is_empty() {
+                return Err("providers need a prefix".into());
+            }
+            if provider.command.trim().is_empty() {
+                return Err(format!("provider `{}` needs a command", provider.prefix));
+            }
+        }
         Ok(())
     }
 
     fn apply_config<'a>(
         &mut self,
-        _engine: &mut Engine<'a>,
+        engine: &mut Engine<'a>,
         config: Self::Config,
         options: &mut Options,
     ) -> bool {
         let size_changed = self.cfg.width != config.width || self.cfg.height != config.height;
         let pos_changed = self.cfg.position != config.position;
         let command_changed = self.cfg.launch_options != config.launch_options;
+        let icons_changed =
+            self.cfg.icon_theme != config.icon_theme || self.cfg.icon_size != config.icon_size;
         self.cfg = config;
-        if size_changed || pos_changed || command_changed {
+        self.scripts = self
+            .cfg
+            .providers
+            .iter()
+            .map(|p| Arc::new(Script::new(p.clone())) as Arc<dyn Provider>)
+            .collect();
+        if icons_changed && self.atlas.is_some() {
+            // Rescanned on the next redraw, with the new icons.
+            self.destroy_atlas(engine);
+            self.apps = Arc::new(Vec::new());
+            self.entries.clear();
+            self.results.clear();
+            self.icons = None;
+        }
+        if size_changed || pos_changed || command_changed || icons_changed {
             if command_changed {
                 let mut launch_options = Vec::new();
                 helpers::parse_exec_args(&self.cfg.launch_options, &mut launch_options);
@@ -192,15 +558,34 @@ impl OrbitModule for Launcher {
     fn update<'a>(
         &mut self,
         _ctl: &mut orbit_api::OrbitCtl,
-        _tid: Option<TargetId>,
+        tid: Option<TargetId>,
         engine: &mut Engine<'a>,
         event: &Event<Self::Message>,
     ) -> Task<Msg> {
+        // Hidden while picking, with `orbit toggle launcher` say: the caller
+        // got `None` then, so the picker is done with.
+        if self.dmenu.as_ref().is_some_and(|dmenu| dmenu.is_answered()) {
+            self.dmenu = None;
+            self.reset();
+        }
         match event {
             Event::RedrawRequested => {
+                let scale = tid
+                    .and_then(|tid| engine.globals(&tid))
+                    .map_or(1.0, |g| g.scale);
                 if self.apps.is_empty() {
+                    self.scale = scale;
+                    self.ensure_atlas(engine);
+                    Task::batch([
+                        Task::blocking(helpers::scan_desktop_files),
+                        self.index_icons(),
+                    ])
+                } else if scale != self.scale {
+                    // Moved to an output with another scale: redo the icons.
+                    self.scale = scale;
+                    self.destroy_atlas(engine);
                     self.ensure_atlas(engine);
-                    Task::spawn(async { helpers::scan_desktop_files().await })
+                    Task::batch([self.load_icons(), self.load_item_icons()])
                 } else {
                     Task::None
                 }
@@ -213,16 +598,7 @@ impl OrbitModule for Launcher {
             }) => match key {
                 LogicalKey::Backspace => {
                     self.query.pop();
-                    self.selected = 0;
-                    if self.query.is_empty() {
-                        self.show_all();
-                        Task::RedrawTarget
-                    } else {
-                        let query = self.query.clone();
-                        let apps = Arc::clone(&self.apps);
-                        let max = self.cfg.max_results;
-                        Task::spawn(async move { helpers::search(&apps, &query, max) })
-                    }
+                    self.requery()
                 }
 
                 LogicalKey::ArrowDown => {
@@ -237,23 +613,37 @@ impl OrbitModule for Launcher {
                     self.select_next(1);
                     Task::RedrawTarget
                 }
+                LogicalKey::ArrowRight => {
+                    if self.expand() {
+                        Task::RedrawTarget
+                    } else {
+                        Task::None
+                    }
+                }
+                LogicalKey::ArrowLeft => self.collapse(),
 
+                LogicalKey::Enter if self.items.is_some() => self.activate_item(),
                 LogicalKey::Enter => {
-                    if let Some(exec) = self.active_exec().map(str::to_owned) {
+                    if let Some(app) = self.active_app().cloned() {
+                        Arc::make_mut(&mut self.history).record(&app.key());
                         self.query.clear();
                         self.show_all();
                         let cmd = self.launch_options.clone();
-                        Task::spawn(async move { helpers::launch_app(exec, cmd).await })
+                        let terminal = self.terminal();
+                        let history = self.history_loaded.then(|| Arc::clone(&self.history));
+                        Task::spawn(async move {
+                            if let Some(history) = history {
+                                orbit_api::unblock(move || history.save()).await;
+                            }
+                            helpers::launch_app(app, cmd, terminal).await
+                        })
                     } else {
                         Task::None
                     }
                 }
 
-                LogicalKey::Escape => {
-                    self.query.clear();
-                    self.show_all();
-                    Task::ExitModule
-                }
+                LogicalKey::Escape if self.expanded.is_some() => self.collapse(),
+                LogicalKey::Escape => self.close(None),
 
                 LogicalKey::Character(_) | LogicalKey::Space => {
                     let c = if let LogicalKey::Character(ch) = key {
@@ -300,11 +690,7 @@ impl OrbitModule for Launcher {
                     };
 
                     self.query.push(ch);
-                    self.selected = 0;
-                    let query = self.query.clone();
-                    let apps = Arc::clone(&self.apps);
-                    let max = self.cfg.max_results;
-                    Task::spawn(async move { helpers::search(&apps, &query, max) })
+                    self.requery()
                 }
 
                 _ => Task::None,
@@ -313,33 +699,83 @@ impl OrbitModule for Launcher {
             Event::Message(Msg::ScannedApps(apps)) => {
                 self.apps = Arc::clone(apps);
                 self.rebuild_entries_from_apps();
-                self.show_all();
+                // A rescan can come in while searching: search the new apps.
+                self.expanded = None;
+                let relist = if self.items.is_some() {
+                    Task::None
+                } else if self.query.is_empty() {
+                    self.show_all();
+                    Task::None
+                } else {
+                    self.search()
+                };
 
-                let icon_size = self.cfg.icon_size;
-                let tasks: Vec<Task<Msg>> = self
-                    .entries
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, e)| e.icon.is_none() && !e.icon_name.is_empty())
-                    .map(|(i, e)| {
-                        let icon_name = e.icon_name.clone();
-                        Task::spawn(
-                            async move { helpers::load_icon(i, &icon_name, icon_size).await },
-                        )
-                    })
-                    .collect();
+                Task::batch([relist, self.load_icons()])
+            }
+
+            // Not scanned yet, or since cleanup: the next show scans anyway.
+            Event::Message(Msg::AppsChanged) if self.apps.is_empty() => Task::None,
+            Event::Message(Msg::AppsChanged) => Task::blocking(helpers::scan_desktop_files),
 
-                Task::batch(tasks)
+            // Stale once the query changed or no provider is picked anymore.
+            Event::Message(Msg::Items(id, _)) if self.items.is_none() || *id != self.query_id => {
+                Task::None
             }
 
-            Event::Message(Msg::Results(indices)) => {
-                self.results = indices.clone();
+            Event::Message(Msg::Items(_, items)) => {
+                self.items = Some(items.clone());
+                self.clamp_selection();
+                Task::batch([self.load_item_icons(), Task::RedrawTarget])
+            }
+
+            Event::Message(Msg::IconsIndexed(icons)) => {
+                self.icons = Some(Arc::clone(icons));
+                Task::batch([self.load_icons(), self.load_item_icons()])
+            }
+
+            // Stale once an app is expanded or a provider picked.
+            Event::Message(Msg::Results(_)) if self.expanded.is_some() || self.items.is_some() => {
+                Task::None
+            }
+
+            Event::Message(Msg::Results(results)) => {
+                self.results = results.clone();
                 self.clamp_selection();
                 Task::RedrawTarget
             }
 
+            // Already in: launches since were recorded on top, and saved.
+            Event::Message(Msg::HistoryLoaded(_)) if self.history_loaded => Task::None,
+            Event::Message(Msg::HistoryLoaded(history)) => {
+                let launched = Arc::clone(&self.history);
+                let mut history = History::clone(history);
+                history.merge(&launched);
+                self.history = Arc::new(history);
+                self.history_loaded = true;
+                // What was launched before it loaded was held back.
+                let save = if launched.is_empty() {
+                    Task::None
+                } else {
+                    let history = Arc::clone(&self.history);
+                    Task::blocking(move || {
+                        history.save();
+                        Msg::HistorySaved
+                    })
+                };
+                let relist = if self.expanded.is_some() || self.items.is_some() {
+                    Task::None
+                } else if self.query.is_empty() {
+                    self.show_all();
+                    Task::RedrawTarget
+                } else {
+                    self.search()
+                };
+                Task::batch([save, relist])
+            }
+
             Event::Message(Msg::IconLoaded(idx, w, h, pixels)) => {
-                if *w == 0 || *h == 0 {
+                // Also drops icons decoded for a scale since moved away from.
+                if *w == 0 || *h == 0 || *w != self.icon_px() {
                     return Task::None;
                 }
                 if let Some(entry) = self.entries.get_mut(*idx)
@@ -347,22 +783,49 @@ impl OrbitModule for Launcher {
                 {
                     entry.icon = engine.load_texture_into_atlas(atlas, *w, *h, pixels.as_slice());
                 }
-                if self.results.contains(idx) {
+                if self.results.iter().any(|m| m.index == *idx) {
                     Task::RedrawTarget
                 } else {
                     Task::None
                 }
             }
 
+            Event::Message(Msg::ItemIconLoaded(name, w, h, pixels)) => {
+                // Not asked for since the atlas was rebuilt, or not found.
+                if *w != self.icon_px() || !self.item_icons.contains_key(name) {
+                    return Task::None;
+                }
+                if let Some(atlas) = &mut self.atlas {
+                    let handle = engine.load_texture_into_atlas(atlas, *w, *h, pixels.as_slice());
+                    self.item_icons.insert(name.clone(), handle);
+                }
+                Task::RedrawTarget
+            }
+
+            Event::Message(Msg::Dmenu(choices)) => {
+                // An earlier picker still open is dismissed by this one.
+                let dmenu = Arc::new(Dmenu::new(choices.clone()));
+                if let Some(dmenu) = self.dmenu.replace(Arc::clone(&dmenu)) {
+                    dmenu.reply(None);
+                }
+                self.query.clear();
+                Task::batch([self.requery(), Task::spawn(dmenu.dismiss_on_hide())])
+            }
+
             Event::Message(Msg::Refresh) => {
                 self.destroy_atlas(engine);
                 self.ensure_atlas(engine);
                 self.apps = Arc::new(Vec::new());
                 self.entries.clear();
                 self.results.clear();
+                self.items = None;
                 self.query.clear();
                 self.selected = 0;
-                Task::spawn(async { helpers::scan_desktop_files().await })
+                self.icons = None;
+                Task::batch([
+                    Task::blocking(helpers::scan_desktop_files),
+                    self.index_icons(),
+                ])
             }
 
             Event::Message(Msg::Launched) => Task::ExitModule,
@@ -371,11 +834,43 @@ impl OrbitModule for Launcher {
         }
     }
 
+    fn subscriptions(&self) -> Subscription<Msg> {
+        // Loaded once while the module is, so hiding it can't drop the
+        // result. Later loads are ignored.
+        let history = Subscription::background(Subscription::stream(|tx| async move {
+            let history = orbit_api::unblock(History::load).await;
+            let _ = tx.send(Msg::HistoryLoaded(Arc::new(history)));
+        }));
+        // Rescans while hidden too, so the apps are current when shown.
+        let rescan = Subscription::background(Subscription::stream(|tx| async move {
+            let (changed_tx, changed) = async_channel::unbounded();
+            let Some(_watcher) = helpers::watch_desktop_files(move || {
+                let _ = changed_tx.try_send(());
+            }) else {
+                return;
+            };
+            while changed.recv().await.is_ok() {
+                // An install touches many files: rescan once it settles.
+                Timer::after(SETTLE).await;
+                while changed.try_recv().is_ok() {}
+                if tx.send(Msg::AppsChanged).is_err() {
+                    return;
+                }
+            }
+        }));
+        Subscription::Batch(vec![history, rescan])
+    }
+
     fn view(&self, _tid: &TargetId, _theme: &orbit_api::ui::theme::Theme) -> Element {
         let icon_sz = self.cfg.icon_size as i32;
 
-        let prompt = if self.query.is_empty() {
-            "  Search applications…".to_owned()
+        let prompt = if self.query.is_empty() && self.dmenu.is_some() {
+            "  Choose…".to_owned()
+        } else if self.query.is_empty() {
+            format!(
+                "  Search applications…  {}",
+                providers::legend(&self.scripts)
+            )
         } else {
             format!("  {}_", self.query)
         };
@@ -388,12 +883,55 @@ impl OrbitModule for Launcher {
             Color::rgba(70, 70, 80, 180),
         );
 
-        let mut results_col = Column::empty().size(Size::new(Length::Grow, Length::Fit));
-        for (row_idx, &entry_idx) in self.results.iter().enumerate() {
-            let Some(entry) = self.entries.get(entry_idx) else {
-                continue;
-            };
+        // Icon, title, its matched characters, subtitle and whether there
+        // are actions to expand, for apps and provider items alike.
+        let rows: Vec<(Element, &str, &[usize], &str, bool)> = match &self.items {
+            Some(items) => items
+                .iter()
+                .map(|item| {
+                    let icon = item
+                        .icon
+                        .as_ref()
+                        .and_then(|name| self.item_icons.get(name).copied().flatten());
+                    let icon: Element = match icon {
+                        Some(handle) => {
+                            Image::new(Size::splat(Length::Fixed(icon_sz)), handle).into()
+                        }
+                        None => glyph(&item.glyph, icon_sz),
+                    };
+                    (icon, &*item.title, &*item.positions, &*item.subtitle, false)
+                })
+                .collect(),
+            None => self
+                .results
+                .iter()
+                .filter_map(|result| {
+                    let entry = self.entries.get(result.index)?;
+                    let icon: Element = if let Some(handle) = entry.icon {
+                        Image::new(Size::splat(Length::Fixed(icon_sz)), handle).into()
+                    } else {
+                        Rectangle::new(
+                            Size::splat(Length::Fixed(icon_sz)),
+                            Color::rgba(70, 70, 80, 180),
+                        )
+                        .into()
+                    };
+                    Some((
+                        icon,
+                        &*entry.name,
+                        &*result.positions,
+                        &*entry.description,
+                        entry.has_actions,
+                    ))
+                })
+                .collect(),
+        };
+        let count = rows.len();
 
+        let mut results_col = Column::empty().size(Size::new(Length::Grow, Length::Fit));
+        for (row_idx, (icon_el, title, positions, subtitle, has_actions)) in
+            rows.into_iter().enumerate()
+        {
             let is_selected = row_idx == self.selected;
             let bg = if is_selected {
                 Color::rgba(60, 120, 220, 200)
@@ -401,16 +939,6 @@ impl OrbitModule for Launcher {
                 Color::rgba(0, 0, 0, 0)
             };
 
-            let icon_el: Element = if let Some(handle) = entry.icon {
-                Image::new(Size::splat(Length::Fixed(icon_sz)), handle).into()
-            } else {
-                Rectangle::new(
-                    Size::splat(Length::Fixed(icon_sz)),
-                    Color::rgba(70, 70, 80, 180),
-                )
-                .into()
-            };
-
             let name_color = Color::rgba(230, 230, 235, 255);
             let desc_color = if is_selected {
                 Color::rgba(190, 205, 240, 200)
@@ -419,21 +947,34 @@ impl OrbitModule for Launcher {
             };
 
             let text_col = Column::new(el![
-                Text::label(entry.name.clone())
-                    .color(name_color)
-                    .size(Size::new(Length::Grow, Length::Fit))
-                    .wrap(Wrap::None),
-                Text::caption(entry.description.clone())
+                highlighted(
+                    title,
+                    positions,
+                    name_color,
+                    Color::rgba(255, 210, 120, 255)
+                ),
+                Text::caption(subtitle.to_owned())
                     .color(desc_color)
                     .size(Size::new(Length::Grow, Length::Fit))
                     .wrap(Wrap::None),
             ])
             .size(Size::new(Length::Grow, Length::Fit));
 
+            // Hint that Right lists the app's actions.
+            let more: Element = if has_actions && self.expanded.is_none() {
+                Text::label("›".to_owned())
+                    .color(desc_color)
+                    .size(Size::splat(Length::Fit))
+                    .into()
+            } else {
+                Spacer::new(Size::splat(Length::Fixed(0))).into()
+            };
+
             let row = Row::new(el![
                 icon_el,
                 Spacer::new(Size::new(Length::Fixed(10), Length::Fit)),
-                text_col
+                text_col,
+                more
             ])
             .size(Size::new(Length::Grow, Length::Fit))
             .padding(Vec4::splat(10))
@@ -442,7 +983,7 @@ impl OrbitModule for Launcher {
             results_col.push(row);
 
             // Thin separator between rows, skip after last.
-            if row_idx + 1 < self.results.len() {
+            if row_idx + 1 < count {
                 results_col.push(Rectangle::new(
                     Size::new(Length::Grow, Length::Fixed(1)),
                     Color::rgba(55, 55, 65, 120),
@@ -450,17 +991,20 @@ impl OrbitModule for Launcher {
             }
         }
 
-        let body: Element = if !self.results.is_empty() {
+        let empty = match self.provider() {
+            Some((provider, _)) => provider.hint().to_owned(),
+            None => "No applications found".to_owned(),
+        };
+        let body: Element = if count > 0 {
             Scrollable::new(results_col)
                 .size(Size::new(Length::Grow, Length::Grow))
                 .into()
         } else if !self.query.is_empty() {
             Column::new(el![
                 Spacer::new(Size::splat(Length::Grow)),
-                Row::new(el![Text::body("No applications found".to_owned())
-                    .color(Color::rgba(130, 130, 145, 200))])
-                .size(Size::new(Length::Grow, Length::Fit))
-                .padding(Vec4::splat(16)),
+                Row::new(el![Text::body(empty).color(Color::rgba(130, 130, 145, 200))])
+                    .size(Size::new(Length::Grow, Length::Fit))
+                    .padding(Vec4::splat(16)),
                 Spacer::new(Size::splat(Length::Grow)),
             ])
             .size(Size::splat(Length::Grow))
@@ -477,6 +1021,60 @@ impl OrbitModule for Launcher {
     }
 }
 
+/// A provider item's glyph, centered in an icon sized square.
+fn glyph(glyph: &str, size: i32) -> Element {
+    let fill = || Spacer::new(Size::splat(Length::Grow));
+    Column::new(el![
+        fill(),
+        Row::new(el![
+            fill(),
+            Text::h3(glyph.to_owned())
+                .color(Color::rgba(230, 230, 235, 255))
+                .size(Size::splat(Length::Fit))
+                .wrap(Wrap::None),
+            fill()
+        ])
+        .size(Size::new(Length::Grow, Length::Fit)),
+        fill()
+    ])
+    .size(Size::splat(Length::Fixed(size)))
+    .color(Color::rgba(70, 70, 80, 180))
+    .into()
+}
+
+/// `name` with the characters at `positions` picked out in `accent`.
+fn highlighted(name: &str, positions: &[usize], color: Color, accent: Color) -> Element {
+    let label = |s: String, color: Color| {
+        Text::label(s)
+            .color(color)
+            .size(Size::splat(Length::Fit))
+            .wrap(Wrap::None)
+    };
+    if positions.is_empty() {
+        return label(name.to_owned(), color)
+            .size(Size::new(Length::Grow, Length::Fit))
+            .into();
+    }
+
+    // Runs of matched and unmatched characters.
+    let mut row = Row::empty().size(Size::new(Length::Grow, Length::Fit));
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (i, c) in name.chars().enumerate() {
+        let matched = positions.contains(&i);
+        if matched != run_matched && !run.is_empty() {
+            let color = if run_matched { accent } else { color };
+            row.push(label(std::mem::take(&mut run), color));
+        }
+        run_matched = matched;
+        run.push(c);
+    }
+    if !run.is_empty() {
+        row.push(label(run, if run_matched { accent } else { color }));
+    }
+    row.into()
+}
+
 orbit_plugin! {
     module: Launcher,
     name: "launcher",
@@ -490,5 +1088,6 @@ orbit_plugin! {
         output: Some(OutputSet::Active),
     }),
     commands: [("refresh", Msg::Refresh)],
+    arg_commands: [("dmenu", Msg::Dmenu)],
     persistent_state: true,
 }
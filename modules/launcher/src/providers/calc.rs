@@ -0,0 +1,166 @@
+use std::{f64::consts, iter::Peekable, str::Chars};
+
+use super::{Action, Item, Provider};
+
+/// `=`: evaluates arithmetic as it's typed, copying the result when picked.
+pub struct Calc;
+
+impl Provider for Calc {
+    fn prefix(&self) -> &str {
+        "="
+    }
+
+    fn name(&self) -> &str {
+        "calc"
+    }
+
+    fn hint(&self) -> &str {
+        "Type an expression, like 2^10 or sqrt(2) * pi"
+    }
+
+    fn query(&self, query: &str, _max: usize) -> Vec<Item> {
+        let Some(value) = eval(query) else {
+            return Vec::new();
+        };
+        let result = format_number(value);
+        vec![Item {
+            title: result.clone(),
+            subtitle: format!("{query} — Enter to copy"),
+            glyph: "=".into(),
+            icon: None,
+            score: 0,
+            positions: Vec::new(),
+            action: Action::copy(result),
+        }]
+    }
+}
+
+/// `None` if `expr` doesn't parse or isn't a finite number.
+fn eval(expr: &str) -> Option<f64> {
+    let mut parser = Parser {
+        chars: expr.chars().peekable(),
+    };
+    let value = parser.expr()?;
+    parser.skip_ws();
+    (parser.chars.peek().is_none() && value.is_finite()).then_some(value)
+}
+
+/// Recursive descent over `+ -`, `* / %`, `^` (right associative), unary
+/// minus, parentheses, constants and one-argument functions.
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_ws(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_ws();
+        self.chars.next_if_eq(&c).is_some()
+    }
+
+    fn expr(&mut self) -> Option<f64> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Option<f64> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') || self.eat('×') {
+                value *= self.unary()?;
+            } else if self.eat('/') || self.eat('÷') {
+                value /= self.unary()?;
+            } else if self.eat('%') {
+                value %= self.unary()?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Option<f64> {
+        if self.eat('-') {
+            return Some(-self.unary()?);
+        }
+        if self.eat('+') {
+            return self.unary();
+        }
+        let base = self.atom()?;
+        if self.eat('^') {
+            // `-` binds looser than `^` on its right too: 2^-1 is 0.5.
+            return Some(base.powf(self.unary()?));
+        }
+        Some(base)
+    }
+
+    fn atom(&mut self) -> Option<f64> {
+        self.skip_ws();
+        if self.eat('(') {
+            let value = self.expr()?;
+            return self.eat(')').then_some(value);
+        }
+        let c = *self.chars.peek()?;
+        if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(c) = self.chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                number.push(c);
+            }
+            return number.parse().ok();
+        }
+        if c.is_alphabetic() {
+            let mut name = String::new();
+            while let Some(c) = self.chars.next_if(|c| c.is_alphanumeric()) {
+                name.push(c);
+            }
+            let name = name.to_lowercase();
+            match name.as_str() {
+                "pi" => return Some(consts::PI),
+                "e" => return Some(consts::E),
+                "tau" => return Some(consts::TAU),
+                _ => {}
+            }
+            let f: fn(f64) -> f64 = match name.as_str() {
+                "sqrt" => f64::sqrt,
+                "cbrt" => f64::cbrt,
+                "abs" => f64::abs,
+                "floor" => f64::floor,
+                "ceil" => f64::ceil,
+                "round" => f64::round,
+                "sin" => f64::sin,
+                "cos" => f64::cos,
+                "tan" => f64::tan,
+                "asin" => f64::asin,
+                "acos" => f64::acos,
+                "atan" => f64::atan,
+                "ln" => f64::ln,
+                "log" => f64::log10,
+                "exp" => f64::exp,
+                _ => return None,
+            };
+            return Some(f(self.atom()?));
+        }
+        None
+    }
+}
+
+/// Integers without a fraction, everything else to 10 significant digits.
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{value:.0}");
+    }
+    let digits = (9 - value.abs().log10().floor() as i32).clamp(0, 15) as usize;
+    let s = format!("{value:.digits$}");
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    s.to_owned()
+}
@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use orbit_api::CommandArgs;
+
+use super::{Action, Item, Provider};
+use crate::fuzzy::fuzzy_match;
+
+/// `orbit command launcher dmenu`: filters the lines it was given, and
+/// answers the caller with the one picked.
+pub struct Dmenu {
+    choices: CommandArgs,
+}
+
+impl Dmenu {
+    pub fn new(choices: CommandArgs) -> Self {
+        Self { choices }
+    }
+
+    /// Answers the caller, `None` when dismissed.
+    pub fn reply(&self, answer: Option<String>) {
+        self.choices.reply(answer);
+    }
+
+    /// `true` once the caller was answered.
+    pub fn is_answered(&self) -> bool {
+        self.choices.is_answered()
+    }
+
+    /// Never resolves, but answers `None` when dropped. Run as a task, it is
+    /// dropped when the launcher gets hidden, by whatever means, so the
+    /// caller isn't left waiting on a picker that is gone.
+    pub fn dismiss_on_hide<M: Send>(self: Arc<Self>) -> impl Future<Output = M> + Send {
+        let guard = Dismiss(self);
+        async move {
+            let _guard = guard;
+            std::future::pending().await
+        }
+    }
+}
+
+/// Answers `None` when dropped.
+struct Dismiss(Arc<Dmenu>);
+
+impl Drop for Dismiss {
+    fn drop(&mut self) {
+        self.0.reply(None);
+    }
+}
+
+impl Provider for Dmenu {
+    /// Takes the whole query, so there is nothing to pick it by.
+    fn prefix(&self) -> &str {
+        ""
+    }
+
+    fn name(&self) -> &str {
+        "choices"
+    }
+
+    fn hint(&self) -> &str {
+        "No matching choices, Enter to pick what's typed"
+    }
+
+    /// Lists every matching choice, not just `max_results`: the caller
+    /// expects to be able to pick any of them.
+    fn query(&self, query: &str, _max: usize) -> Vec<Item> {
+        let mut items: Vec<Item> = self
+            .choices
+            .args
+            .iter()
+            .filter_map(|choice| {
+                let (score, positions) = if query.is_empty() {
+                    (0, Vec::new())
+                } else {
+                    fuzzy_match(query, choice)?
+                };
+                Some(Item {
+                    title: choice.clone(),
+                    subtitle: String::new(),
+                    glyph: "•".into(),
+                    icon: None,
+                    score,
+                    positions,
+                    action: Action::Reply(choice.clone()),
+                })
+            })
+            .collect();
+        // Stable, so equally good matches keep the order they were given in.
+        items.sort_by_key(|item| std::cmp::Reverse(item.score));
+        items
+    }
+}
@@ -0,0 +1,78 @@
+use super::{Action, Item, Provider, top};
+use crate::fuzzy::fuzzy_match_fields;
+
+/// `:`: searches emoji by name and shortcode, or picks any character by
+/// code point like `:U+2192`. Picking one copies it.
+pub struct Emoji;
+
+fn item(
+    symbol: String,
+    title: String,
+    subtitle: String,
+    score: i32,
+    positions: Vec<usize>,
+) -> Item {
+    Item {
+        action: Action::copy(symbol.clone()),
+        glyph: symbol,
+        icon: None,
+        title,
+        subtitle,
+        score,
+        positions,
+    }
+}
+
+impl Provider for Emoji {
+    fn prefix(&self) -> &str {
+        ":"
+    }
+
+    fn name(&self) -> &str {
+        "emoji"
+    }
+
+    fn hint(&self) -> &str {
+        "No matching emoji"
+    }
+
+    fn query(&self, query: &str, max: usize) -> Vec<Item> {
+        if query.is_empty() {
+            return Vec::new();
+        }
+        if let Some(hex) = query
+            .strip_prefix("U+")
+            .or_else(|| query.strip_prefix("u+"))
+        {
+            let Some(c) = u32::from_str_radix(hex, 16).ok().and_then(char::from_u32) else {
+                return Vec::new();
+            };
+            return vec![item(
+                c.to_string(),
+                format!("U+{:04X}", u32::from(c)),
+                "Character".into(),
+                0,
+                Vec::new(),
+            )];
+        }
+
+        let items = emojis::iter()
+            .filter_map(|emoji| {
+                let (score, positions) =
+                    fuzzy_match_fields(query, emoji.name(), emoji.shortcodes())?;
+                let subtitle = emoji
+                    .shortcode()
+                    .map(|code| format!(":{code}:"))
+                    .unwrap_or_default();
+                Some(item(
+                    emoji.as_str().to_owned(),
+                    emoji.name().to_owned(),
+                    subtitle,
+                    score,
+                    positions,
+                ))
+            })
+            .collect();
+        top(items, max)
+    }
+}
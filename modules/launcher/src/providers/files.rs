@@ -0,0 +1,71 @@
+use std::path::PathBuf;
+
+use super::{Action, Item, Provider, top};
+use crate::fuzzy::fuzzy_match;
+
+/// `/`: browses files under `$HOME`. The query is a path relative to it,
+/// its last part fuzzy-matched against the directory it's in.
+pub struct Files;
+
+impl Provider for Files {
+    fn prefix(&self) -> &str {
+        "/"
+    }
+
+    fn name(&self) -> &str {
+        "files"
+    }
+
+    fn hint(&self) -> &str {
+        "No matching files"
+    }
+
+    fn query(&self, query: &str, max: usize) -> Vec<Item> {
+        let Some(home) = std::env::var_os("HOME").map(PathBuf::from) else {
+            return Vec::new();
+        };
+        let (dir, partial) = match query.rfind('/') {
+            Some(i) => query.split_at(i + 1),
+            None => ("", query),
+        };
+        let Ok(rd) = std::fs::read_dir(home.join(dir)) else {
+            return Vec::new();
+        };
+
+        let items = rd
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                if name.starts_with('.') && !partial.starts_with('.') {
+                    return None;
+                }
+                let (score, positions) = if partial.is_empty() {
+                    (0, Vec::new())
+                } else {
+                    fuzzy_match(partial, &name)?
+                };
+                let path = entry.path();
+                let is_dir = path.is_dir();
+                Some(Item {
+                    title: if is_dir {
+                        format!("{name}/")
+                    } else {
+                        name.clone()
+                    },
+                    subtitle: format!("~/{dir}{name}"),
+                    glyph: if is_dir { "/" } else { "•" }.into(),
+                    icon: None,
+                    // Directories first among equally good matches.
+                    score: score * 2 + i32::from(is_dir),
+                    positions,
+                    action: if is_dir {
+                        Action::Query(format!("/{dir}{name}/"))
+                    } else {
+                        Action::Spawn(vec!["xdg-open".into(), path.to_string_lossy().into()])
+                    },
+                })
+            })
+            .collect();
+        top(items, max)
+    }
+}
@@ -0,0 +1,113 @@
+//! Launcher modes other than searching apps, picked by a query prefix.
+
+use std::sync::{Arc, OnceLock};
+
+use orbit_api::BoxFuture;
+
+mod calc;
+mod dmenu;
+mod emoji;
+mod files;
+mod run;
+mod script;
+mod windows;
+
+pub use dmenu::Dmenu;
+pub use script::Script;
+
+/// What happens when an item is picked.
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Run these arguments, wrapped in the launch options, and close.
+    Spawn(Vec<String>),
+    /// Replace the query, like when entering a directory.
+    Query(String),
+    /// Answer the `dmenu` caller with this line and close.
+    Reply(String),
+    /// Focus a window and close.
+    Activate(windows::Activate),
+}
+
+impl Action {
+    /// Copies `text` to the clipboard.
+    pub fn copy(text: impl Into<String>) -> Self {
+        Self::Spawn(vec!["wl-copy".into(), "--".into(), text.into()])
+    }
+}
+
+/// One result of a provider.
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub title: String,
+    pub subtitle: String,
+    /// Text shown where apps have their icon.
+    pub glyph: String,
+    /// Icon name or path shown in place of `glyph` once loaded.
+    pub icon: Option<String>,
+    pub score: i32,
+    /// Char indices of `title` the query matched, for highlighting.
+    pub positions: Vec<usize>,
+    pub action: Action,
+}
+
+pub trait Provider: Send + Sync + 'static {
+    /// What a query starts with to pick this provider, like `>`.
+    fn prefix(&self) -> &str;
+    /// What the search bar's placeholder calls it, like `run`.
+    fn name(&self) -> &str;
+    /// Shown when there is nothing to list.
+    fn hint(&self) -> &str;
+    /// Items for `query`, the prefix stripped, best first and at most `max`.
+    fn query(&self, query: &str, max: usize) -> Vec<Item>;
+    /// What the launcher awaits for `query`, dropped when a newer query
+    /// takes over. By default `query` on the blocking pool, which runs to
+    /// the end regardless.
+    fn items(self: Arc<Self>, query: String, max: usize) -> BoxFuture<Vec<Item>> {
+        Box::pin(orbit_api::unblock(move || self.query(&query, max)))
+    }
+}
+
+/// The providers that ship with the launcher.
+pub fn builtin() -> &'static [Arc<dyn Provider>] {
+    static BUILTIN: OnceLock<Vec<Arc<dyn Provider>>> = OnceLock::new();
+    BUILTIN.get_or_init(|| {
+        vec![
+            Arc::new(run::Run::default()),
+            Arc::new(calc::Calc),
+            Arc::new(files::Files),
+            Arc::new(emoji::Emoji),
+            Arc::new(windows::Windows::default()),
+        ]
+    })
+}
+
+/// The prefixes `scripts` and the built-in providers they leave reachable
+/// answer to, with their names, like `> run  = calc`.
+pub fn legend(scripts: &[Arc<dyn Provider>]) -> String {
+    let shadowed =
+        |p: &Arc<dyn Provider>| scripts.iter().any(|s| p.prefix().starts_with(s.prefix()));
+    scripts
+        .iter()
+        .chain(builtin().iter().filter(|p| !shadowed(p)))
+        .map(|p| format!("{} {}", p.prefix(), p.name()))
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+/// The provider `query` picks, with the query it gets. `None` for apps.
+pub fn find<'q>(
+    providers: &[Arc<dyn Provider>],
+    query: &'q str,
+) -> Option<(Arc<dyn Provider>, &'q str)> {
+    providers.iter().find_map(|p| {
+        let rest = query.strip_prefix(p.prefix())?;
+        Some((Arc::clone(p), rest.trim_start()))
+    })
+}
+
+/// Sorts `items` best first and keeps the top `max`.
+fn top(mut items: Vec<Item>, max: usize) -> Vec<Item> {
+    items.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.title.cmp(&b.title)));
+    items.truncate(max);
+    items
+}
@@ -0,0 +1,85 @@
+use std::{collections::BTreeSet, os::unix::fs::PermissionsExt, sync::OnceLock};
+
+use super::{Action, Item, Provider, top};
+use crate::fuzzy::fuzzy_match;
+
+/// `>`: runs the query with `sh -c`, offering programs on `$PATH` while the
+/// first word is typed.
+#[derive(Default)]
+pub struct Run {
+    /// Programs on `$PATH`, listed on first use.
+    programs: OnceLock<Vec<String>>,
+}
+
+impl Run {
+    fn programs(&self) -> &[String] {
+        self.programs.get_or_init(|| {
+            let Some(paths) = std::env::var_os("PATH") else {
+                return Vec::new();
+            };
+            let mut programs = BTreeSet::new();
+            for dir in std::env::split_paths(&paths) {
+                let Ok(rd) = std::fs::read_dir(dir) else {
+                    continue;
+                };
+                for entry in rd.flatten() {
+                    let executable = entry
+                        .metadata()
+                        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0);
+                    if executable && let Ok(name) = entry.file_name().into_string() {
+                        programs.insert(name);
+                    }
+                }
+            }
+            programs.into_iter().collect()
+        })
+    }
+}
+
+fn shell(command: &str) -> Action {
+    Action::Spawn(vec!["sh".into(), "-c".into(), command.into()])
+}
+
+impl Provider for Run {
+    fn prefix(&self) -> &str {
+        ">"
+    }
+
+    fn name(&self) -> &str {
+        "run"
+    }
+
+    fn hint(&self) -> &str {
+        "Type a command to run"
+    }
+
+    fn query(&self, query: &str, max: usize) -> Vec<Item> {
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut items = vec![Item {
+            title: query.to_owned(),
+            subtitle: "Run in shell".into(),
+            glyph: ">".into(),
+            icon: None,
+            score: i32::MAX,
+            positions: Vec::new(),
+            action: shell(query),
+        }];
+        if !query.contains(char::is_whitespace) {
+            items.extend(self.programs().iter().filter_map(|program| {
+                let (score, positions) = fuzzy_match(query, program)?;
+                (program != query).then(|| Item {
+                    title: program.clone(),
+                    subtitle: "Program".into(),
+                    glyph: ">".into(),
+                    icon: None,
+                    score,
+                    positions,
+                    action: shell(program),
+                })
+            }));
+        }
+        top(items, max)
+    }
+}
@@ -0,0 +1,119 @@
+use std::{path::Path, sync::Arc, time::Duration};
+
+use async_io::Timer;
+use async_process::{Command, Stdio};
+use futures_lite::{AsyncWriteExt, future};
+use orbit_api::{BoxFuture, serde::Deserialize};
+
+use super::{Action, Item, Provider};
+use crate::config::{ScriptInput, ScriptProvider};
+
+/// How long a command gets to print its results before it is killed.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A provider from the config, running its command on every query.
+pub struct Script {
+    cfg: ScriptProvider,
+    name: String,
+}
+
+impl Script {
+    pub fn new(cfg: ScriptProvider) -> Self {
+        let name = cfg.name.clone().unwrap_or_else(|| {
+            cfg.command
+                .split_whitespace()
+                .next()
+                .and_then(|program| Path::new(program).file_stem())
+                .map_or_else(
+                    || "script".to_owned(),
+                    |stem| stem.to_string_lossy().into_owned(),
+                )
+        });
+        Self { cfg, name }
+    }
+
+    /// Run the command for `query`. Dropping this kills it.
+    async fn run(&self, query: &str, max: usize) -> Vec<Item> {
+        let timeout = async {
+            Timer::after(TIMEOUT).await;
+            None
+        };
+        let Some(stdout) = future::or(self.output(query), timeout).await else {
+            return Vec::new();
+        };
+
+        // Listed in the order printed, lines that don't parse skipped.
+        String::from_utf8_lossy(&stdout)
+            .lines()
+            .filter_map(|line| orbit_api::serde_json::from_str::<Line>(line).ok())
+            .take(max)
+            .map(|line| Item {
+                title: line.title,
+                subtitle: line.subtitle,
+                glyph: self.cfg.prefix.clone(),
+                icon: line.icon.filter(|i| !i.is_empty()),
+                score: 0,
+                positions: Vec::new(),
+                action: Action::Spawn(vec!["sh".into(), "-c".into(), line.exec]),
+            })
+            .collect()
+    }
+
+    async fn output(&self, query: &str) -> Option<Vec<u8>> {
+        let mut cmd = Command::new("sh");
+        // `$0` for the script, the query as `$1`.
+        cmd.args(["-c", self.cfg.command.as_str(), "sh"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+        match self.cfg.input {
+            ScriptInput::Arg => {
+                cmd.arg(query).stdin(Stdio::null());
+            }
+            ScriptInput::Stdin => {
+                cmd.stdin(Stdio::piped());
+            }
+        }
+        let mut child = cmd.spawn().ok()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // Dropped right after, so the command sees the end of input.
+            let _ = stdin.write_all(format!("{query}\n").as_bytes()).await;
+        }
+        Some(child.output().await.ok()?.stdout)
+    }
+}
+
+/// One line of the command's output.
+#[derive(Deserialize)]
+#[serde(crate = "orbit_api::serde")]
+struct Line {
+    title: String,
+    #[serde(default)]
+    subtitle: String,
+    /// Icon name or absolute path.
+    icon: Option<String>,
+    /// Run with `sh -c` when picked.
+    exec: String,
+}
+
+impl Provider for Script {
+    fn prefix(&self) -> &str {
+        &self.cfg.prefix
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn hint(&self) -> &str {
+        self.cfg.hint.as_deref().unwrap_or("No results")
+    }
+
+    fn query(&self, query: &str, max: usize) -> Vec<Item> {
+        future::block_on(self.run(query, max))
+    }
+
+    fn items(self: Arc<Self>, query: String, max: usize) -> BoxFuture<Vec<Item>> {
+        Box::pin(async move { self.run(&query, max).await })
+    }
+}
@@ -0,0 +1,218 @@
+use std::sync::Mutex;
+
+use wayland_client::{
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle, event_created_child,
+    protocol::{wl_registry, wl_seat::WlSeat},
+};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::{
+    zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1 as Handle},
+    zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1 as Manager},
+};
+
+use super::{Action, Item, Provider, top};
+use crate::fuzzy::fuzzy_match_fields;
+
+/// `,`: switches to an open window. Windows are listed through the
+/// wlr-foreign-toplevel protocol, so compositors without it list none.
+#[derive(Default)]
+pub struct Windows {
+    /// Connected on the first query and kept, so later ones only need a
+    /// roundtrip to catch up.
+    session: Mutex<Option<Session>>,
+}
+
+struct Session {
+    conn: Connection,
+    queue: EventQueue<State>,
+    state: State,
+}
+
+impl Session {
+    fn connect() -> Option<Self> {
+        let conn = Connection::connect_to_env().ok()?;
+        let mut queue = conn.new_event_queue();
+        conn.display().get_registry(&queue.handle(), ());
+        let mut state = State::default();
+        // Globals first, then the windows the manager announces once bound.
+        queue.roundtrip(&mut state).ok()?;
+        queue.roundtrip(&mut state).ok()?;
+        Some(Self { conn, queue, state })
+    }
+}
+
+#[derive(Default)]
+struct State {
+    manager: Option<Manager>,
+    seat: Option<WlSeat>,
+    /// Open windows, in the order they were announced.
+    windows: Vec<Window>,
+}
+
+struct Window {
+    handle: Handle,
+    title: String,
+    app_id: String,
+}
+
+/// Focuses a listed window.
+#[derive(Debug, Clone)]
+pub struct Activate {
+    conn: Connection,
+    seat: WlSeat,
+    handle: Handle,
+}
+
+impl Activate {
+    pub fn run(&self) {
+        self.handle.activate(&self.seat);
+        let _ = self.conn.flush();
+    }
+}
+
+impl Provider for Windows {
+    fn prefix(&self) -> &str {
+        ","
+    }
+
+    fn name(&self) -> &str {
+        "windows"
+    }
+
+    fn hint(&self) -> &str {
+        "No matching windows"
+    }
+
+    fn query(&self, query: &str, max: usize) -> Vec<Item> {
+        let mut session = self.session.lock().unwrap();
+        if session.is_none() {
+            *session = Session::connect();
+        }
+        let Some(Session { conn, queue, state }) = session.as_mut() else {
+            return Vec::new();
+        };
+        if queue.roundtrip(state).is_err() {
+            // The compositor went away; connect again next time.
+            *session = None;
+            return Vec::new();
+        }
+        let Some(seat) = &state.seat else {
+            return Vec::new();
+        };
+
+        let items = state
+            .windows
+            .iter()
+            .filter_map(|window| {
+                let title = if window.title.is_empty() {
+                    &window.app_id
+                } else {
+                    &window.title
+                };
+                let (score, positions) = if query.is_empty() {
+                    (0, Vec::new())
+                } else {
+                    fuzzy_match_fields(query, title, [window.app_id.as_str()])?
+                };
+                Some(Item {
+                    title: title.clone(),
+                    subtitle: window.app_id.clone(),
+                    glyph: ",".into(),
+                    icon: (!window.app_id.is_empty()).then(|| window.app_id.clone()),
+                    score,
+                    positions,
+                    action: Action::Activate(Activate {
+                        conn: conn.clone(),
+                        seat: seat.clone(),
+                        handle: window.handle.clone(),
+                    }),
+                })
+            })
+            .collect();
+        top(items, max)
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, ()> for State {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        let wl_registry::Event::Global {
+            name,
+            interface,
+            version,
+        } = event
+        else {
+            return;
+        };
+        if interface == Manager::interface().name && state.manager.is_none() {
+            state.manager = Some(registry.bind(name, version.min(3), qh, ()));
+        } else if interface == WlSeat::interface().name && state.seat.is_none() {
+            state.seat = Some(registry.bind(name, 1, qh, ()));
+        }
+    }
+}
+
+impl Dispatch<WlSeat, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &WlSeat,
+        _: <WlSeat as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<Manager, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &Manager,
+        event: zwlr_foreign_toplevel_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zwlr_foreign_toplevel_manager_v1::Event::Toplevel { toplevel } = event {
+            state.windows.push(Window {
+                handle: toplevel,
+                title: String::new(),
+                app_id: String::new(),
+            });
+        }
+    }
+
+    event_created_child!(State, Manager, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => (Handle, ()),
+    ]);
+}
+
+impl Dispatch<Handle, ()> for State {
+    fn event(
+        state: &mut Self,
+        handle: &Handle,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        use zwlr_foreign_toplevel_handle_v1::Event;
+
+        let Some(i) = state.windows.iter().position(|w| &w.handle == handle) else {
+            return;
+        };
+        match event {
+            Event::Title { title } => state.windows[i].title = title,
+            Event::AppId { app_id } => state.windows[i].app_id = app_id,
+            Event::Closed => {
+                state.windows.remove(i).handle.destroy();
+            }
+            _ => {}
+        }
+    }
+}
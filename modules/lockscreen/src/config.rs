@@ -12,6 +12,9 @@ fn default_blur() -> u32 {
 fn default_bg_tint() -> u8 {
     0x5e
 }
+fn default_follow_wallpaper() -> bool {
+    true
+}
 
 #[orbit_config]
 pub struct Config {
@@ -23,6 +26,9 @@ pub struct Config {
     pub blur: u32,
     #[serde(default = "default_bg_tint")]
     pub bg_tint: u8,
+    /// Tint with the wallpaper's dominant color, as bright as `bg_tint`.
+    #[serde(default = "default_follow_wallpaper")]
+    pub follow_wallpaper: bool,
 }
 
 impl Default for Config {
@@ -32,6 +38,7 @@ impl Default for Config {
             idle: default_idle_duration(),
             blur: default_blur(),
             bg_tint: default_bg_tint(),
+            follow_wallpaper: default_follow_wallpaper(),
         }
     }
 }
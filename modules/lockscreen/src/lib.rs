@@ -4,7 +4,8 @@ use std::{
 };
 
 use orbit_api::{
-    Engine, Event, Lease, OrbitCtl, OrbitModule, Subscription, Task, orbit_plugin,
+    Broadcast, BroadcastKind, Engine, Event, Lease, OrbitCtl, OrbitModule, Subscription, Task,
+    orbit_plugin,
     ui::{
         el,
         event::{KeyEvent, KeyState, LogicalKey},
@@ -16,7 +17,7 @@ use orbit_api::{
         widget::{Column, ContentFit, Element, Length, Overlay, Rectangle, Row, Spacer, Text},
     },
 };
-use orbit_keys::WALLPAPER_TEX;
+use orbit_keys::{Palette, SESSION_LOCKED, WALLPAPER_PALETTE, WALLPAPER_TEX};
 use pam::Client;
 
 mod config;
@@ -85,8 +86,11 @@ pub struct LockScreen {
     username: String,
     password: String,
     state: AuthState,
+    /// Whether `SESSION_LOCKED` was published for this showing.
+    locked: bool,
 
     bg: HashMap<TargetId, Lease<TextureHandle>>,
+    palette: HashMap<TargetId, Palette>,
 
     last_event: Instant,
     idle_time: Option<Duration>,
@@ -102,7 +106,9 @@ impl Default for LockScreen {
             username: current_username(),
             password: Default::default(),
             state: Default::default(),
+            locked: false,
             bg: HashMap::new(),
+            palette: HashMap::new(),
             last_event: Instant::now(),
             idle_time: None,
             idle: false,
@@ -119,6 +125,30 @@ impl LockScreen {
         {
             self.bg.insert(tid, lease);
         }
+        if let Some(tid) = ctl.target()
+            && !self.palette.contains_key(&tid)
+            && let Some(palette) = ctl.get(WALLPAPER_PALETTE)
+        {
+            self.palette.insert(tid, palette);
+        }
+    }
+
+    /// The color the wallpaper is tinted towards at full blur.
+    fn tint_for(&self, tid: &TargetId) -> [f32; 3] {
+        let gray = self.cfg.bg_tint as f32;
+        let dominant = self
+            .palette
+            .get(tid)
+            .filter(|_| self.cfg.follow_wallpaper)
+            .and_then(Palette::dominant);
+        match dominant {
+            Some(rgb) => {
+                // Keep the hue, scaled so the brightest channel is `bg_tint`.
+                let max = rgb.into_iter().max().unwrap_or(0).max(1) as f32;
+                rgb.map(|c| c as f32 / max * gray)
+            }
+            None => [gray; 3],
+        }
     }
 }
 
@@ -129,6 +159,7 @@ impl OrbitModule for LockScreen {
     fn cleanup<'a>(&mut self, _engine: &mut Engine<'a>) {
         self.password.clear();
         self.state = AuthState::Idle;
+        self.locked = false;
         self.blur_strength = 0.0;
     }
 
@@ -212,7 +243,7 @@ impl OrbitModule for LockScreen {
                     let password = std::mem::take(&mut self.password);
                     Task::batch([
                         Task::RedrawTarget,
-                        Task::spawn(async move {
+                        Task::blocking(move || {
                             let ok = authenticate(&username, password);
                             Msg::AuthResult(ok)
                         }),
@@ -250,7 +281,8 @@ impl OrbitModule for LockScreen {
                 Msg::AuthResult(true) => {
                     self.password.clear();
                     self.state = AuthState::Idle;
-                    Task::ExitModule
+                    self.locked = false;
+                    Task::batch([Task::publish(SESSION_LOCKED, false), Task::ExitModule])
                 }
                 Msg::AuthResult(false) => {
                     self.password.clear();
@@ -285,6 +317,11 @@ impl OrbitModule for LockScreen {
             self.last_event = Instant::now();
         }
 
+        if !self.locked {
+            self.locked = true;
+            task = Task::batch([task, Task::publish(SESSION_LOCKED, true)]);
+        }
+
         task
     }
 
@@ -292,16 +329,35 @@ impl OrbitModule for LockScreen {
         &mut self,
         ctl: &mut OrbitCtl<'_>,
         _tid: Option<orbit_api::ui::graphics::TargetId>,
-        key: &'static str,
+        broadcast: &Broadcast,
     ) -> Task<Self::Message> {
-        if key == WALLPAPER_TEX.id
-            && let Some(tid) = ctl.target()
-            && let Some(lease) = ctl.lease(WALLPAPER_TEX)
-        {
-            self.bg.insert(tid, lease);
+        let Some(tid) = ctl.target() else {
+            return Task::None;
+        };
+
+        if broadcast.key == WALLPAPER_PALETTE.id {
+            match ctl.get(WALLPAPER_PALETTE) {
+                Some(palette) => self.palette.insert(tid, palette),
+                None => self.palette.remove(&tid),
+            };
             return Task::RedrawTarget;
         }
-        Task::None
+        if broadcast.key != WALLPAPER_TEX.id {
+            return Task::None;
+        }
+
+        match broadcast.kind {
+            BroadcastKind::Published => {
+                let Some(lease) = ctl.lease(WALLPAPER_TEX) else {
+                    return Task::None;
+                };
+                self.bg.insert(tid, lease);
+            }
+            BroadcastKind::Revoked => {
+                self.bg.remove(&tid);
+            }
+        }
+        Task::RedrawTarget
     }
 
     fn view(&self, tid: &TargetId, _theme: &orbit_api::ui::theme::Theme) -> Element {
@@ -352,10 +408,10 @@ impl OrbitModule for LockScreen {
                         .fit(ContentFit::Cover)
                         .tint({
                             let max_blur = self.cfg.blur as f32;
-                            let scaled_gray = (255.0
-                                - (255.0 - self.cfg.bg_tint as f32) * self.blur_strength / max_blur)
-                                as u8;
-                            Color::rgb(scaled_gray, scaled_gray, scaled_gray)
+                            let [r, g, b] = self.tint_for(tid).map(|c| {
+                                (255.0 - (255.0 - c) * self.blur_strength / max_blur) as u8
+                            });
+                            Color::rgb(r, g, b)
                         })
                         .strength(self.blur_strength),
                     column.size(Size::splat(Length::Grow))
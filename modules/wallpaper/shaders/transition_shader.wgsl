@@ -0,0 +1,80 @@
+const SLOT_BITS: u32 = 12u;
+const SLOT_MASK: u32 = (1u << SLOT_BITS) - 1u;
+const GEN_BITS: u32 = 32u - SLOT_BITS;
+const GEN_MASK: u32 = (1u << GEN_BITS) - 1u;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) size: vec2<f32>,
+    @location(2) style: vec4<u32>,
+    @location(3) tex: vec4<u32>,
+    @location(10) uv: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) uv_tex: vec2<f32>,
+    @location(2) @interpolate(flat) slot_plus_one: u32,
+    @location(3) @interpolate(flat) gen: u32,
+}
+
+struct Globals {
+    window_size: vec2<f32>,
+    mouse_pos: vec2<f32>,
+    mouse_buttons: u32,
+    time: f32,
+    delta_time: f32,
+    frame: u32,
+    scale: f32,
+}
+
+var<push_constant> globals: Globals;
+
+@group(0) @binding(0) var tex_arr: binding_array<texture_2d<f32>>;
+@group(0) @binding(1) var samp: sampler;
+@group(0) @binding(2) var<storage, read> gens: array<u32>;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    let uv = vec2<f32>(in.uv.x, 1.0 - in.uv.y);
+    let world_pos = in.position + (uv * in.size);
+
+    let ndc = vec2<f32>(
+        (world_pos.x / globals.window_size.x) * 2.0 - 1.0,
+        1.0 - (world_pos.y / globals.window_size.y) * 2.0
+    );
+
+    let packed = in.tex.x;
+    let scale = unpack2x16unorm(in.tex.y);
+    let offs = unpack2x16unorm(in.tex.z);
+
+    // The quad may only show part of the image (cover cropping, or the
+    // visible strip of a slide/wipe), given as a uv window into the texture.
+    let uv_min = unpack2x16unorm(in.style.y);
+    let uv_max = unpack2x16unorm(in.style.z);
+    let window_uv = mix(uv_min, uv_max, uv);
+
+    var out: VertexOutput;
+    out.position = vec4<f32>(ndc, 0.0, 1.0);
+    out.slot_plus_one = packed & SLOT_MASK;
+    out.gen = packed >> SLOT_BITS;
+    out.color = unpack4x8unorm(in.style.x);
+    out.uv_tex = (window_uv * scale) + offs;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let idx = in.slot_plus_one - 1u;
+
+    if in.slot_plus_one == 0u || (gens[idx] & GEN_MASK) != in.gen {
+        return vec4<f32>(0.0);
+    }
+
+    let color = textureSample(tex_arr[idx], samp, in.uv_tex) * in.color;
+
+    // Premultiplied, matching the UI blend state
+    return vec4<f32>(color.rgb * color.a, color.a);
+}
@@ -0,0 +1,117 @@
+use std::{
+    fs,
+    io::Cursor,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use async_channel::{Receiver, Sender};
+use image::{
+    AnimationDecoder, DynamicImage, Frames, ImageFormat,
+    codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder},
+};
+
+use crate::{Msg, decode::Request};
+
+/// Frames asking for less are shown this long; GIFs often say 0 and mean
+/// "as fast as the viewer likes".
+const MIN_DELAY: Duration = Duration::from_millis(20);
+
+/// The frames of `data` if it is an animation, `None` for a still image.
+fn frames(data: Vec<u8>, format: ImageFormat) -> Option<Frames<'static>> {
+    let reader = Cursor::new(data);
+    match format {
+        ImageFormat::Gif => Some(GifDecoder::new(reader).ok()?.into_frames()),
+        ImageFormat::Png => {
+            let png = PngDecoder::new(reader).ok()?;
+            if !png.is_apng().ok()? {
+                return None;
+            }
+            Some(png.apng().ok()?.into_frames())
+        }
+        ImageFormat::WebP => {
+            let webp = WebPDecoder::new(reader).ok()?;
+            if !webp.has_animation() {
+                return None;
+            }
+            Some(webp.into_frames())
+        }
+        _ => None,
+    }
+}
+
+/// More than one frame, so single-frame GIFs stay still images.
+pub fn is_animated(data: &[u8], format: ImageFormat) -> bool {
+    frames(data.to_vec(), format).is_some_and(|f| f.take(2).filter(Result::is_ok).count() == 2)
+}
+
+/// Plays one animated wallpaper on one output, on the blocking pool.
+///
+/// Frames are fitted like still images and sent as `Msg::Frame` to
+/// `frames`, which the module's stream subscription drains, each only once
+/// the previous one was drawn (a token on `demand`) and its delay has
+/// passed. The module hands out tokens from `RedrawRequested`, so playback
+/// stalls on its own while the output isn't repainted, and withholds them
+/// while the session is locked. Dropping the token sender stops the player.
+pub struct Player {
+    pub id: u64,
+    pub req: Request,
+    pub format: ImageFormat,
+    pub min_interval: Duration,
+    pub demand: Receiver<()>,
+    pub frames: Sender<Msg>,
+}
+
+impl Player {
+    /// Play until stopped or the file no longer decodes. Blocks throughout,
+    /// so this goes through `Task::blocking`.
+    pub fn run(self) -> Msg {
+        self.play();
+        Msg::Stopped(self.id)
+    }
+
+    fn play(&self) {
+        let Ok(data) = fs::read(&self.req.file) else {
+            return;
+        };
+
+        // The first frame is already on screen from the still decode.
+        let mut skip_first = true;
+        let mut due = Instant::now();
+        loop {
+            let Some(frames) = frames(data.clone(), self.format) else {
+                return;
+            };
+            for frame in frames {
+                let Ok(frame) = frame else {
+                    return;
+                };
+                let delay = Duration::from(frame.delay())
+                    .max(MIN_DELAY)
+                    .max(self.min_interval);
+                if skip_first {
+                    skip_first = false;
+                    due = Instant::now() + delay;
+                    continue;
+                }
+
+                let rgba = self.req.fit(DynamicImage::ImageRgba8(frame.into_buffer()));
+                if self.demand.recv_blocking().is_err() {
+                    return;
+                }
+                if let Some(wait) = due.checked_duration_since(Instant::now()) {
+                    thread::sleep(wait);
+                }
+                if self
+                    .frames
+                    .try_send(Msg::Frame(self.id, Arc::new(rgba)))
+                    .is_err()
+                {
+                    return;
+                }
+                due = Instant::now() + delay;
+            }
+        }
+    }
+}
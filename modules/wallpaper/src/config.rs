@@ -1,16 +1,7 @@
-use std::{borrow::Cow, path::PathBuf, time::Duration};
-
-use chrono::{DateTime, Local};
-use orbit_api::{
-    orbit_config,
-    ui::{
-        model::{Family, Size},
-        text::Wrap,
-        widget::{Column, Element, Length, Overlay, Row, Text},
-    },
-};
+use std::{borrow::Cow, collections::HashMap, path::PathBuf, time::Duration};
 
-use crate::PerTarget;
+use image::imageops::FilterType;
+use orbit_api::{orbit_config, ui::model::Family};
 
 fn default_source() -> PathBuf {
     xdg_user::pictures()
@@ -21,13 +12,47 @@ fn default_source() -> PathBuf {
 fn default_cycle() -> String {
     "1h".into()
 }
+fn default_transition_duration() -> String {
+    "600ms".into()
+}
+fn default_background() -> String {
+    "#000000".into()
+}
+fn default_max_fps() -> u32 {
+    30
+}
 
 #[orbit_config]
 pub struct Config {
     #[serde(default = "default_source")]
     pub source: PathBuf,
+    /// `source` overrides keyed by output name, used in `per_output` mode.
+    #[serde(default)]
+    pub outputs: HashMap<String, PathBuf>,
+    #[serde(default)]
+    pub order: Order,
+    #[serde(default)]
+    pub mode: Mode,
+    #[serde(default)]
+    pub fit: Fit,
+    /// `#rrggbb` or `#rrggbbaa`, behind images that don't cover the output.
+    #[serde(default = "default_background")]
+    pub background: String,
+    #[serde(default)]
+    pub filter: Filter,
+    /// Upper bound on how often animated wallpapers advance.
+    #[serde(default = "default_max_fps")]
+    pub max_fps: u32,
     #[serde(default = "default_cycle")]
     pub cycle: String,
+    /// Sources taking over `source` from a time of day on.
+    #[serde(default)]
+    pub schedule: Vec<ScheduleEntry>,
+    /// Where sunrise and sunset in `schedule` are computed for.
+    #[serde(default)]
+    pub location: Option<Location>,
+    #[serde(default)]
+    pub transition: TransitionConfig,
     #[serde(default)]
     pub widgets: Vec<Placed>,
 }
@@ -36,42 +61,285 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             source: default_source(),
+            outputs: HashMap::new(),
+            order: Order::default(),
+            mode: Mode::default(),
+            fit: Fit::default(),
+            background: default_background(),
+            filter: Filter::default(),
+            max_fps: default_max_fps(),
             cycle: default_cycle(),
+            schedule: Vec::new(),
+            location: None,
+            transition: TransitionConfig::default(),
             widgets: Vec::new(),
         }
     }
 }
 
+impl Config {
+    /// Every directory (or file) images are picked from in the current mode.
+    pub fn roots(&self) -> Vec<PathBuf> {
+        let mut roots = vec![self.source.clone()];
+        for entry in &self.schedule {
+            if !roots.contains(&entry.source) {
+                roots.push(entry.source.clone());
+            }
+        }
+        if self.mode == Mode::PerOutput {
+            for root in self.outputs.values() {
+                if !roots.contains(root) {
+                    roots.push(root.clone());
+                }
+            }
+        }
+        roots
+    }
+
+    pub fn background(&self) -> [u8; 4] {
+        parse_hex_color(&self.background).unwrap_or([0, 0, 0, 255])
+    }
+}
+
+/// `#rrggbb` or `#rrggbbaa`.
+pub fn parse_hex_color(s: &str) -> Option<[u8; 4]> {
+    let hex = s.strip_prefix('#')?;
+    if !hex.is_ascii() || !matches!(hex.len(), 6 | 8) {
+        return None;
+    }
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    let alpha = if hex.len() == 8 { byte(6)? } else { 255 };
+    Some([byte(0)?, byte(2)?, byte(4)?, alpha])
+}
+
+#[orbit_config]
+pub struct ScheduleEntry {
+    /// `HH:MM`, or `sunrise`/`sunset` with an optional offset like `sunset-30m`.
+    pub at: String,
+    /// Directory or single image file shown from `at` until the next entry.
+    pub source: PathBuf,
+}
+
+#[orbit_config]
+#[derive(Copy, PartialEq)]
+pub struct Location {
+    pub latitude: f64,
+    /// East is positive.
+    pub longitude: f64,
+}
+
+/// How the next image is chosen from a source.
+#[orbit_config]
+#[derive(Copy, Default, PartialEq)]
+pub enum Order {
+    /// Shuffled, avoiding images already shown on another output.
+    #[default]
+    Random,
+    /// In path order, directories included.
+    Sequential,
+    /// In file name order, ignoring the directories.
+    Name,
+    /// Oldest modification time first.
+    Mtime,
+}
+
+#[orbit_config]
+#[derive(Copy, Default, PartialEq)]
+pub enum Mode {
+    /// Every output picks its own image from its own source.
+    #[default]
+    PerOutput,
+    /// One image from `source`, shown whole on every output.
+    Same,
+    /// One image from `source` covering the layout of all outputs, each
+    /// showing its own slice of it.
+    Span,
+}
+
+/// How an image is sized to its output. Ignored in `span` mode, where the
+/// image always covers the layout.
+#[orbit_config]
+#[derive(Copy, Default, PartialEq)]
+pub enum Fit {
+    /// Scaled to cover the output, cropping what overflows.
+    #[default]
+    Fill,
+    /// Scaled to fit inside the output, letterboxed with `background`.
+    #[serde(alias = "letterbox")]
+    Fit,
+    /// Scaled to the output's size, ignoring the aspect ratio.
+    Stretch,
+    /// Unscaled and centered.
+    Center,
+    /// Unscaled and repeated from the top left corner.
+    Tile,
+}
+
+/// Resampling filter used when scaling images.
+#[orbit_config]
+#[derive(Copy, Default, PartialEq)]
+pub enum Filter {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    #[default]
+    Lanczos3,
+}
+
+impl From<Filter> for FilterType {
+    fn from(filter: Filter) -> Self {
+        match filter {
+            Filter::Nearest => FilterType::Nearest,
+            Filter::Triangle => FilterType::Triangle,
+            Filter::CatmullRom => FilterType::CatmullRom,
+            Filter::Gaussian => FilterType::Gaussian,
+            Filter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+#[orbit_config]
+pub struct TransitionConfig {
+    #[serde(default)]
+    pub kind: TransitionKind,
+    #[serde(default = "default_transition_duration")]
+    pub duration: String,
+}
+
+impl Default for TransitionConfig {
+    fn default() -> Self {
+        Self {
+            kind: TransitionKind::default(),
+            duration: default_transition_duration(),
+        }
+    }
+}
+
+impl TransitionConfig {
+    /// `None` when images should be swapped without animating.
+    pub fn duration(&self) -> Option<Duration> {
+        let d: Duration = self.duration.parse::<humantime::Duration>().ok()?.into();
+        (self.kind != TransitionKind::None && !d.is_zero()).then_some(d)
+    }
+}
+
+#[orbit_config]
+#[derive(Copy, Default, PartialEq)]
+pub enum TransitionKind {
+    None,
+    #[default]
+    Fade,
+    /// The new image pushes the old one out to the left.
+    Slide,
+    /// The new image is revealed left to right over the old one.
+    Wipe,
+}
+
 #[orbit_config]
 pub struct Placed {
     #[serde(default)]
     pub x: f32,
     #[serde(default)]
     pub y: f32,
+    /// Edge or corner of the output to stick to, instead of `x`/`y`.
+    #[serde(default)]
+    pub anchor: Option<Anchor>,
+    /// Distance from the anchored edges, in pixels.
+    #[serde(default)]
+    pub margin: i32,
     #[serde(flatten)]
     pub widget: WidgetConfig,
 }
 
-impl Placed {
-    pub fn place(&self, target: &PerTarget, now: &DateTime<Local>, on: &mut Overlay) {
-        on.push(
-            self.widget.element(now),
-            (target.size.width as f32 * self.x.clamp(0.0, 1.0)).ceil() as i32,
-            (target.size.height as f32 * self.y.clamp(0.0, 1.0)).ceil() as i32,
-        );
+#[orbit_config]
+#[derive(Copy, PartialEq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Horizontal and vertical alignment on the output.
+    pub fn aligns(self) -> (Align, Align) {
+        use Align::*;
+        match self {
+            Anchor::TopLeft => (Start, Start),
+            Anchor::Top => (Center, Start),
+            Anchor::TopRight => (End, Start),
+            Anchor::Left => (Start, Center),
+            Anchor::Center => (Center, Center),
+            Anchor::Right => (End, Center),
+            Anchor::BottomLeft => (Start, End),
+            Anchor::Bottom => (Center, End),
+            Anchor::BottomRight => (End, End),
+        }
     }
 }
 
+#[orbit_config]
+#[derive(Copy, Default, PartialEq)]
+pub enum Align {
+    #[default]
+    #[serde(alias = "left")]
+    Start,
+    Center,
+    #[serde(alias = "right")]
+    End,
+}
+
 fn default_clock_font_size() -> f32 {
     48.0
 }
+fn default_text_font_size() -> f32 {
+    24.0
+}
 fn default_time_format() -> String {
     "%H:%M".to_string()
 }
+fn default_date_format() -> String {
+    "%A, %-d %B".to_string()
+}
+fn default_command_interval() -> String {
+    "10s".into()
+}
+
+#[orbit_config]
+pub struct WidgetConfig {
+    #[serde(flatten)]
+    pub style: Style,
+    #[serde(flatten)]
+    pub kind: WidgetKind,
+}
+
+/// Looks shared by every widget type.
+#[orbit_config]
+#[derive(Default)]
+pub struct Style {
+    /// Text color, `#rrggbb` or `#rrggbbaa`.
+    #[serde(default)]
+    pub color: Option<String>,
+    /// How the widget lines up with its `x` position, or inside its column.
+    #[serde(default)]
+    pub align: Align,
+    /// Space around the widget, in pixels.
+    #[serde(default)]
+    pub padding: i32,
+    /// Fill behind the widget and its padding, `#rrggbb` or `#rrggbbaa`.
+    #[serde(default)]
+    pub background: Option<String>,
+}
 
 #[orbit_config]
 #[serde(tag = "type")]
-pub enum WidgetConfig {
+pub enum WidgetKind {
     Clock {
         #[serde(default = "default_clock_font_size")]
         font_size: f32,
@@ -80,6 +348,41 @@ pub enum WidgetConfig {
         #[serde(default = "default_time_format")]
         time_format: String,
     },
+    Date {
+        #[serde(default = "default_text_font_size")]
+        font_size: f32,
+        #[serde(default)]
+        font_family: Option<FontFamilyConfig>,
+        #[serde(default = "default_date_format")]
+        format: String,
+    },
+    /// Fixed text. `{user}`, `{host}`, `{output}`, `{wallpaper}` and
+    /// `{env:NAME}` are filled in.
+    Text {
+        text: String,
+        #[serde(default = "default_text_font_size")]
+        font_size: f32,
+        #[serde(default)]
+        font_family: Option<FontFamilyConfig>,
+    },
+    /// The output of a shell command, run again every `interval`.
+    Command {
+        command: String,
+        #[serde(default = "default_command_interval")]
+        interval: String,
+        #[serde(default = "default_text_font_size")]
+        font_size: f32,
+        #[serde(default)]
+        font_family: Option<FontFamilyConfig>,
+    },
+    /// An image file, scaled down to fit `width` and `height` if given.
+    Image {
+        path: PathBuf,
+        #[serde(default)]
+        width: Option<u32>,
+        #[serde(default)]
+        height: Option<u32>,
+    },
     Column {
         #[serde(default)]
         spacing: i32,
@@ -93,67 +396,54 @@ pub enum WidgetConfig {
 }
 
 impl WidgetConfig {
-    pub fn contains_clock(&self) -> bool {
-        match self {
-            Self::Clock { .. } => true,
-            Self::Column { children, .. } | Self::Row { children, .. } => {
-                children.iter().any(Self::contains_clock)
+    /// Call `f` with this widget and everything nested in it.
+    pub fn walk<'a>(&'a self, f: &mut impl FnMut(&'a WidgetConfig)) {
+        f(self);
+        if let WidgetKind::Column { children, .. } | WidgetKind::Row { children, .. } = &self.kind {
+            for child in children {
+                child.walk(f);
             }
         }
     }
 
+    pub fn contains_clock(&self) -> bool {
+        let mut found = false;
+        self.walk(&mut |w| {
+            found |= matches!(w.kind, WidgetKind::Clock { .. } | WidgetKind::Date { .. });
+        });
+        found
+    }
+
     pub fn clock_durations(&self, out: &mut Vec<Duration>) {
-        match self {
-            Self::Clock { time_format: f, .. } => out.push(if f.contains("%f") {
-                Duration::from_millis(100)
-            } else if f.contains("%S") {
-                Duration::from_secs(1)
-            } else if f.contains("%M") {
-                Duration::from_secs(60)
-            } else {
-                Duration::from_secs(3600)
-            }),
-            Self::Column { children, .. } | Self::Row { children, .. } => {
-                for c in children {
-                    c.clock_durations(out);
-                }
-            }
-        }
+        self.walk(&mut |w| match &w.kind {
+            WidgetKind::Clock { time_format: f, .. } | WidgetKind::Date { format: f, .. } => out
+                .push(if f.contains("%f") {
+                    Duration::from_millis(100)
+                } else if f.contains("%S") {
+                    Duration::from_secs(1)
+                } else if f.contains("%M") {
+                    Duration::from_secs(60)
+                } else {
+                    Duration::from_secs(3600)
+                }),
+            _ => {}
+        });
     }
 
-    fn element(&self, now: &DateTime<Local>) -> Element {
-        match self {
-            WidgetConfig::Clock {
-                font_size,
-                font_family,
-                time_format,
-            } => {
-                let time = now.format(time_format).to_string();
-                let mut text = Text::new(time)
-                    .wrap(Wrap::None)
-                    .font_size(*font_size)
-                    .family(Family::Monospace)
-                    .size(Size::splat(Length::Fit));
-                if let Some(family) = font_family {
-                    text = text.family(family.clone().into());
-                }
-                text.into()
-            }
-            WidgetConfig::Column { spacing, children } => {
-                let mut col = Column::empty().spacing(*spacing);
-                for child in children {
-                    col.push(child.element(now));
-                }
-                col.into()
+    /// Every command to run, with how often.
+    pub fn commands(&self, out: &mut HashMap<String, Duration>) {
+        self.walk(&mut |w| {
+            if let WidgetKind::Command {
+                command, interval, ..
+            } = &w.kind
+                && let Ok(every) = interval.parse::<humantime::Duration>()
+            {
+                let every: Duration = every.into();
+                out.entry(command.clone())
+                    .and_modify(|d| *d = (*d).min(every))
+                    .or_insert(every);
             }
-            WidgetConfig::Row { spacing, children } => {
-                let mut row = Row::empty().spacing(*spacing);
-                for child in children {
-                    row.push(child.element(now));
-                }
-                row.into()
-            }
-        }
+        });
     }
 }
 
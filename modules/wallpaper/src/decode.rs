@@ -0,0 +1,167 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Arc, OnceLock},
+};
+
+use image::{
+    DynamicImage, ImageFormat, Rgba, RgbaImage,
+    imageops::{self, FilterType},
+};
+use orbit_keys::Palette;
+use resvg::{tiny_skia, usvg};
+
+use crate::{
+    animation,
+    config::Fit,
+    format::{self, Format},
+    palette,
+    span::Span,
+};
+
+/// Largest side an SVG is rasterized at.
+const MAX_SVG_SIDE: f32 = 16384.0;
+
+/// Everything needed to turn an image file into an output's pixels,
+/// detached from the module so it can run on a worker.
+#[derive(Clone)]
+pub struct Request {
+    pub file: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    pub span: Option<Span>,
+    pub fit: Fit,
+    pub filter: FilterType,
+    pub background: [u8; 4],
+}
+
+#[derive(Debug)]
+pub struct Decoded {
+    /// `width * height` RGBA8 pixels. The first frame for animations.
+    pub rgba: Vec<u8>,
+    /// Set when the image is animated, for starting an `animation::Player`.
+    pub animation: Option<ImageFormat>,
+    pub palette: Palette,
+}
+
+impl Request {
+    /// `None` if the file can't be decoded.
+    pub fn decode(&self) -> Option<Decoded> {
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+        let data = fs::read(&self.file).ok()?;
+        let (img, animation) = match format::sniff(&data)? {
+            Format::Raster(format) => (
+                image::load_from_memory_with_format(&data, format).ok()?,
+                animation::is_animated(&data, format).then_some(format),
+            ),
+            Format::Svg => (self.render_svg(&data)?, None),
+        };
+        let rgba = self.fit(img);
+        Some(Decoded {
+            palette: palette::extract(&rgba),
+            rgba,
+            animation,
+        })
+    }
+
+    /// Size `img` to the output as configured, as RGBA8 pixels.
+    pub fn fit(&self, img: DynamicImage) -> Vec<u8> {
+        let (w, h) = (self.width, self.height);
+        if let Some(span) = self.span {
+            let img = span.crop(&img).resize_to_fill(w, h, self.filter);
+            return img.to_rgba8().into_raw();
+        }
+
+        let out = match self.fit {
+            Fit::Fill => img.resize_to_fill(w, h, self.filter).to_rgba8(),
+            Fit::Stretch => img.resize_exact(w, h, self.filter).to_rgba8(),
+            Fit::Fit => self.centered(&img.resize(w, h, self.filter).to_rgba8()),
+            Fit::Center => self.centered(&img.to_rgba8()),
+            Fit::Tile => {
+                let tile = img.to_rgba8();
+                let mut canvas = self.canvas();
+                if tile.width() > 0 && tile.height() > 0 {
+                    for y in (0..h).step_by(tile.height() as usize) {
+                        for x in (0..w).step_by(tile.width() as usize) {
+                            imageops::overlay(&mut canvas, &tile, x.into(), y.into());
+                        }
+                    }
+                }
+                canvas
+            }
+        };
+        out.into_raw()
+    }
+
+    /// Rasterize at the size the fit will scale to, so vector images stay
+    /// sharp at the output's physical resolution.
+    fn render_svg(&self, data: &[u8]) -> Option<DynamicImage> {
+        static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+        let fontdb = FONTS.get_or_init(|| {
+            let mut db = usvg::fontdb::Database::new();
+            db.load_system_fonts();
+            Arc::new(db)
+        });
+        let opts = usvg::Options {
+            fontdb: Arc::clone(fontdb),
+            ..Default::default()
+        };
+        let tree = usvg::Tree::from_data(data, &opts).ok()?;
+
+        let (sw, sh) = (tree.size().width(), tree.size().height());
+        let (w, h) = (self.width as f32, self.height as f32);
+        let (sx, sy) = match (self.span, self.fit) {
+            (Some(span), _) => {
+                let s = span.cover_scale(sw, sh, self.width);
+                (s, s)
+            }
+            (None, Fit::Fill) => {
+                let s = (w / sw).max(h / sh);
+                (s, s)
+            }
+            (None, Fit::Fit) => {
+                let s = (w / sw).min(h / sh);
+                (s, s)
+            }
+            (None, Fit::Stretch) => (w / sw, h / sh),
+            (None, Fit::Center | Fit::Tile) => (1.0, 1.0),
+        };
+        // Keep within the size limit without distorting.
+        let clamp = (MAX_SVG_SIDE / (sw * sx))
+            .min(MAX_SVG_SIDE / (sh * sy))
+            .min(1.0);
+        let (sx, sy) = (sx * clamp, sy * clamp);
+
+        let mut pixmap = tiny_skia::Pixmap::new(
+            ((sw * sx).ceil() as u32).max(1),
+            ((sh * sy).ceil() as u32).max(1),
+        )?;
+        resvg::render(
+            &tree,
+            tiny_skia::Transform::from_scale(sx, sy),
+            &mut pixmap.as_mut(),
+        );
+
+        let mut img = RgbaImage::new(pixmap.width(), pixmap.height());
+        for (dst, src) in img.pixels_mut().zip(pixmap.pixels()) {
+            let c = src.demultiply();
+            *dst = Rgba([c.red(), c.green(), c.blue(), c.alpha()]);
+        }
+        Some(img.into())
+    }
+
+    fn canvas(&self) -> RgbaImage {
+        RgbaImage::from_pixel(self.width, self.height, Rgba(self.background))
+    }
+
+    /// `img` centered on the background, cropped if it's larger.
+    fn centered(&self, img: &RgbaImage) -> RgbaImage {
+        let mut canvas = self.canvas();
+        let x = (i64::from(self.width) - i64::from(img.width())) / 2;
+        let y = (i64::from(self.height) - i64::from(img.height())) / 2;
+        imageops::overlay(&mut canvas, img, x, y);
+        canvas
+    }
+}
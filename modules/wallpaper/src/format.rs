@@ -0,0 +1,45 @@
+use std::{fs::File, io::Read, path::Path};
+
+use image::ImageFormat;
+
+/// Raster formats wallpapers may be in, as enabled on the `image` dependency.
+const RASTER: &[ImageFormat] = &[
+    ImageFormat::Png,
+    ImageFormat::Jpeg,
+    ImageFormat::WebP,
+    ImageFormat::Avif,
+    ImageFormat::Bmp,
+    ImageFormat::Tiff,
+    ImageFormat::Qoi,
+    ImageFormat::Gif,
+];
+
+/// Enough to get past an XML prolog, doctype and comments to the `<svg`.
+const HEAD_LEN: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Raster(ImageFormat),
+    Svg,
+}
+
+/// What the first bytes of a file say it is, whatever its extension.
+pub fn sniff(head: &[u8]) -> Option<Format> {
+    if let Ok(format) = image::guess_format(head) {
+        return RASTER.contains(&format).then_some(Format::Raster(format));
+    }
+
+    let head = head.strip_prefix(b"\xef\xbb\xbf").unwrap_or(head);
+    let text = String::from_utf8_lossy(head);
+    (text.trim_start().starts_with('<') && text.contains("<svg")).then_some(Format::Svg)
+}
+
+pub fn sniff_file(path: &Path) -> Option<Format> {
+    let mut head = Vec::with_capacity(HEAD_LEN);
+    File::open(path)
+        .ok()?
+        .take(HEAD_LEN as u64)
+        .read_to_end(&mut head)
+        .ok()?;
+    sniff(&head)
+}
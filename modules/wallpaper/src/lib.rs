@@ -1,11 +1,15 @@
 use rand::seq::SliceRandom;
 use std::{
     collections::{HashMap, HashSet},
-    fs,
     path::{Path, PathBuf},
-    time::Duration,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
+use async_channel::{Receiver, Sender};
+use image::ImageFormat;
+use notify::RecommendedWatcher;
+
 use orbit_api::{
     Engine, Event, Lease, OrbitCtl, OrbitModule, OutputInfo, Subscription, Task, orbit_plugin,
     ui::{
@@ -17,23 +21,114 @@ use orbit_api::{
         widget::{ContentFit, Element, Image, Length, Overlay, Rectangle},
     },
 };
-use orbit_keys::WALLPAPER_TEX;
+use orbit_keys::{Palette, SESSION_LOCKED, WALLPAPER_PALETTE, WALLPAPER_PATH, WALLPAPER_TEX};
 
 use config::*;
+use span::Span;
+use widgets::*;
 
+mod animation;
 mod config;
+mod decode;
+mod format;
+mod overlay;
+mod palette;
+mod pipeline;
+mod schedule;
+mod source;
+mod span;
+mod widgets;
 
 #[derive(Clone, Debug)]
 pub enum Msg {
     Tick,
     Cycle,
+    Decoded(u64, Option<Arc<decode::Decoded>>),
+    /// A source was scanned on a worker, for the index epoch given.
+    Indexed(u64, PathBuf, Arc<HashSet<PathBuf>>),
+    Frame(u64, Arc<Vec<u8>>),
+    /// The animation player with this id ended.
+    Stopped(u64),
+    Locked(bool),
+    /// The stream subscription is up, so the sources are watched again.
+    Watch,
+    SourceChanged(Arc<source::Changes>),
+    Rescan,
+    /// A `command` widget's command and what it printed.
+    CommandOutput(String, String),
+    /// A `schedule` entry may have started: see whether it took over.
+    Schedule,
 }
 
 pub struct PerTarget {
     size: Size<u32>,
     file: PathBuf,
-    tex: Lease<TextureHandle>,
+    /// `None` until the first image for this output has been decoded. The
+    /// current frame while an animation plays.
+    tex: Option<Lease<TextureHandle>>,
     out: OutputInfo,
+    span: Option<Span>,
+    incoming: Option<Incoming>,
+    loading: Option<Loading>,
+    /// Set when `file` is animated.
+    animation: Option<ImageFormat>,
+    player: Option<PlayerHandle>,
+}
+
+/// The module's end of an `animation::Player`. Dropping it stops the player.
+struct PlayerHandle {
+    id: u64,
+    size: Size<u32>,
+    demand: Sender<()>,
+}
+
+/// A channel from work running outside `update` to one of the stream
+/// subscriptions.
+struct Inbox<T> {
+    tx: Sender<T>,
+    rx: Receiver<T>,
+}
+
+impl<T> Default for Inbox<T> {
+    fn default() -> Self {
+        let (tx, rx) = async_channel::unbounded();
+        Self { tx, rx }
+    }
+}
+
+/// A decode running on a worker for this output. Only the latest request
+/// counts: results carrying an older id are dropped.
+struct Loading {
+    id: u64,
+    file: PathBuf,
+    size: Size<u32>,
+    swap: Swap,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Swap {
+    /// Replace the current image as soon as the new one is decoded.
+    Now,
+    /// Animate towards it with the configured transition.
+    Transition,
+}
+
+/// The image being transitioned to. Its texture is owned here, unpublished,
+/// until the animation ends: only then is it published and leased in place
+/// of `PerTarget::tex`, so readers of `WALLPAPER_TEX` switch exactly once.
+struct Incoming {
+    file: PathBuf,
+    animation: Option<ImageFormat>,
+    palette: Palette,
+    tex: TextureHandle,
+    start: Instant,
+    duration: Duration,
+}
+
+impl Incoming {
+    fn progress(&self) -> f32 {
+        self.start.elapsed().as_secs_f32() / self.duration.as_secs_f32()
+    }
 }
 
 #[derive(Default, Debug)]
@@ -47,45 +142,63 @@ pub struct Wallpaper {
 
     cfg: Config,
     targets: HashMap<TargetId, PerTarget>,
+    /// Last image picked from each source, to continue from in sorted orders.
+    last_picked: HashMap<PathBuf, PathBuf>,
+    /// The image shown on every output in `same` and `span` mode.
+    shared: Option<PathBuf>,
+    /// Source of the `schedule` entry in effect, replacing `source`.
+    scheduled: Option<PathBuf>,
+    next_load: u64,
+    index: source::Index,
+    /// Outputs shown before their source was indexed, added once it is.
+    waiting: HashMap<TargetId, OutputInfo>,
+    /// Sources whose outputs move on to their next image once indexed.
+    deferred: HashSet<PathBuf>,
+    watcher: Option<RecommendedWatcher>,
+    /// Textures of `image` widgets, `None` until loaded.
+    overlay_images: Option<HashMap<overlay::ImageKey, TextureHandle>>,
+    command_outputs: HashMap<String, String>,
+
+    /// Frames from animation players.
+    frames: Inbox<Msg>,
+    /// Paths reported by `watcher`.
+    changes: Inbox<Vec<PathBuf>>,
+    locked: bool,
 }
 
 impl Wallpaper {
-    fn is_supported_ext(p: &Path) -> bool {
-        p.extension()
-            .and_then(|x| x.to_str())
-            .map(|x| matches!(&x.to_ascii_lowercase()[..], "jpg" | "jpeg" | "png"))
-            .unwrap_or(false)
-    }
-
-    fn collect_images_rec(path: &Path, out: &mut Vec<PathBuf>) {
-        if path.is_file() {
-            if Self::is_supported_ext(path) {
-                out.push(path.to_path_buf());
-            }
-            return;
-        }
-
-        let Ok(rd) = fs::read_dir(path) else {
-            return;
-        };
-        for e in rd.flatten() {
-            let p = e.path();
-            if p.is_dir() {
-                Self::collect_images_rec(&p, out);
-            } else if p.is_file() && Self::is_supported_ext(&p) {
-                out.push(p);
-            }
+    fn pick_image(&mut self, root: &Path) -> Option<PathBuf> {
+        if !root.exists() {
+            return None;
         }
+        let all = self.index.images(root, self.cfg.order)?;
+        let picked = if self.cfg.order == Order::Random {
+            self.pick_random_image_unique(all)
+        } else {
+            source::next_after(&all, self.last_picked.get(root).map(PathBuf::as_path))
+        }?;
+        self.last_picked.insert(root.to_path_buf(), picked.clone());
+        Some(picked)
     }
 
-    fn pick_random_image_unique(&self, root: &Path) -> Option<PathBuf> {
-        let mut all = Vec::new();
-        Self::collect_images_rec(root, &mut all);
+    fn pick_random_image_unique(&self, all: Vec<PathBuf>) -> Option<PathBuf> {
         if all.is_empty() {
             return None;
         }
 
-        let used: HashSet<&Path> = self.targets.values().map(|t| t.file.as_path()).collect();
+        let used: HashSet<&Path> = self
+            .targets
+            .values()
+            .flat_map(|t| {
+                [
+                    Some(&t.file),
+                    t.incoming.as_ref().map(|i| &i.file),
+                    t.loading.as_ref().map(|l| &l.file),
+                ]
+            })
+            .flatten()
+            .map(PathBuf::as_path)
+            .collect();
         let mut rng = rand::rng();
 
         let mut candidates: Vec<PathBuf> = all
@@ -101,70 +214,450 @@ impl Wallpaper {
         candidates.into_iter().next()
     }
 
+    /// Where images are picked from for every output without its own source.
+    fn source(&self) -> PathBuf {
+        self.scheduled
+            .clone()
+            .unwrap_or_else(|| self.cfg.source.clone())
+    }
+
+    fn source_for(&self, out: &OutputInfo) -> PathBuf {
+        out.name
+            .as_ref()
+            .and_then(|name| self.cfg.outputs.get(name))
+            .cloned()
+            .unwrap_or_else(|| self.source())
+    }
+
+    /// Where the image of `out` comes from in the current mode.
+    fn root_for(&self, out: &OutputInfo) -> PathBuf {
+        match self.cfg.mode {
+            Mode::PerOutput => self.source_for(out),
+            Mode::Same | Mode::Span => self.source(),
+        }
+    }
+
+    /// Scan the sources the index was asked for but doesn't have yet. The
+    /// images come back as `Msg::Indexed`.
+    fn scans(&mut self) -> Task<Msg> {
+        Task::batch(self.index.start_scans().into_iter().map(|(epoch, root)| {
+            Task::blocking(move || {
+                let found = source::scan(&root);
+                Msg::Indexed(epoch, root, Arc::new(found))
+            })
+        }))
+    }
+
+    fn scheduled_now(cfg: &Config) -> Option<PathBuf> {
+        schedule::active(&cfg.schedule, cfg.location.as_ref(), chrono::Local::now())
+            .map(Path::to_path_buf)
+    }
+
+    /// The image a newly shown output starts with.
+    fn image_for(&mut self, out: &OutputInfo) -> Option<PathBuf> {
+        match self.cfg.mode {
+            Mode::PerOutput => {
+                let root = self.source_for(out);
+                self.pick_image(&root)
+            }
+            Mode::Same | Mode::Span => {
+                if self.shared.is_none() {
+                    let root = self.source();
+                    self.shared = self.pick_image(&root);
+                }
+                self.shared.clone()
+            }
+        }
+    }
+
+    fn span_for(&self, out: &OutputInfo) -> Option<Span> {
+        if self.cfg.mode != Mode::Span {
+            return None;
+        }
+        Span::of(out, self.targets.values().map(|t| &t.out))
+    }
+
     fn place_tex(
         ctl: &mut OrbitCtl<'_>,
         out: &OutputInfo,
+        file: &Path,
         tex: TextureHandle,
     ) -> Option<Lease<TextureHandle>> {
         ctl.publish_on(out, WALLPAPER_TEX, tex);
+        if ctl.get_on(out, WALLPAPER_PATH).as_deref() != Some(file) {
+            ctl.publish_data_on(out, WALLPAPER_PATH, file.to_path_buf());
+        }
         ctl.lease_on(out, WALLPAPER_TEX)
     }
 
-    fn load_texture<'a>(
-        file: &Path,
-        width: u32,
-        height: u32,
-        engine: &mut Engine<'a>,
-    ) -> Option<TextureHandle> {
-        if let Ok(reader) = image::ImageReader::open(file)
-            && let Ok(mut img) = reader.decode()
-        {
-            img = img.resize_to_fill(width, height, image::imageops::FilterType::Nearest);
-            let rgba = img.to_rgba8();
-            let handle = engine.load_texture_rgba8(width, height, rgba.as_raw());
-            Some(handle)
-        } else {
-            None
+    fn place_palette(ctl: &mut OrbitCtl<'_>, out: &OutputInfo, palette: Palette) {
+        if ctl.get_on(out, WALLPAPER_PALETTE).as_ref() != Some(&palette) {
+            ctl.publish_data_on(out, WALLPAPER_PALETTE, palette);
+        }
+    }
+
+    /// Decode `file` for `tid` on a worker. The pixels come back as
+    /// `Msg::Decoded`.
+    fn request(&mut self, tid: &TargetId, file: PathBuf, swap: Swap) -> Task<Msg> {
+        let Some(target) = self.targets.get_mut(tid) else {
+            return Task::None;
+        };
+        self.next_load += 1;
+        let id = self.next_load;
+        let req = Self::decode_request(&self.cfg, target, file.clone());
+        target.loading = Some(Loading {
+            id,
+            file,
+            size: target.size,
+            swap,
+        });
+        Task::blocking(move || Msg::Decoded(id, req.decode().map(Arc::new)))
+    }
+
+    fn decode_request(cfg: &Config, target: &PerTarget, file: PathBuf) -> decode::Request {
+        decode::Request {
+            file,
+            width: target.size.width,
+            height: target.size.height,
+            span: target.span,
+            fit: cfg.fit,
+            filter: cfg.filter.into(),
+            background: cfg.background(),
         }
     }
 
-    fn ensure_texture_loaded(
+    /// Upload the pixels of request `id` and swap them in. `false` if the
+    /// request was superseded or failed.
+    fn finish_load(
         &mut self,
         ctl: &mut OrbitCtl<'_>,
-        tid: &TargetId,
-        out: OutputInfo,
+        id: u64,
+        decoded: Option<&decode::Decoded>,
         engine: &mut Engine<'_>,
     ) -> bool {
-        if self.targets.contains_key(tid) || !self.cfg.source.exists() {
+        let Some(target) = self
+            .targets
+            .values_mut()
+            .find(|t| t.loading.as_ref().is_some_and(|l| l.id == id))
+        else {
             return false;
-        }
-        let Some(path) = self.pick_random_image_unique(&self.cfg.source) else {
+        };
+        let Some(loading) = target.loading.take() else {
             return false;
         };
-        let Some(globals) = engine.globals(tid) else {
+        let Some(decoded) = decoded else {
             return false;
         };
+
+        let (w, h) = (loading.size.width, loading.size.height);
+        let tex = engine.load_texture_rgba8(w, h, &decoded.rgba);
+        Self::finish_transition(ctl, Some(&mut *target));
+        if loading.swap == Swap::Transition
+            && target.tex.is_some()
+            && let Some(duration) = self.cfg.transition.duration()
+        {
+            target.incoming = Some(Incoming {
+                file: loading.file,
+                animation: decoded.animation,
+                palette: decoded.palette.clone(),
+                tex,
+                start: Instant::now(),
+                duration,
+            });
+        } else if let Some(lease) = Self::place_tex(ctl, &target.out, &loading.file, tex) {
+            target.tex = Some(lease);
+            target.file = loading.file;
+            target.animation = decoded.animation;
+            target.player = None;
+            Self::place_palette(ctl, &target.out, decoded.palette.clone());
+        }
+        true
+    }
+
+    fn add_target(
+        &mut self,
+        tid: &TargetId,
+        out: OutputInfo,
+        engine: &mut Engine<'_>,
+    ) -> Task<Msg> {
+        if self.targets.contains_key(tid) {
+            return Task::None;
+        }
+        let Some(globals) = engine.globals(tid) else {
+            return Task::None;
+        };
         let sf = globals.scale;
         let (w, h) = (
             (globals.window_size[0] * sf).ceil() as u32,
             (globals.window_size[1] * sf).ceil() as u32,
         );
-        let Some(tex) = Self::load_texture(&path, w, h, engine) else {
-            return false;
-        };
-        let Some(lease) = Self::place_tex(ctl, &out, tex) else {
-            return false;
+        let Some(path) = self.image_for(&out) else {
+            if self.index.is_pending(&self.root_for(&out)) {
+                self.waiting.insert(*tid, out);
+            }
+            return self.scans();
         };
+        let span = self.span_for(&out);
         self.targets.insert(
             *tid,
             PerTarget {
                 size: Size::new(w, h),
-                tex: lease,
-                file: path,
+                tex: None,
+                file: path.clone(),
                 out,
+                span,
+                incoming: None,
+                loading: None,
+                animation: None,
+                player: None,
             },
         );
-        true
+        Task::batch([self.request(tid, path, Swap::Now), self.respan()])
+    }
+
+    /// Cut every output's slice again after the output layout changed in
+    /// `span` mode.
+    fn respan(&mut self) -> Task<Msg> {
+        if self.cfg.mode != Mode::Span {
+            return Task::None;
+        }
+        let outs: Vec<OutputInfo> = self.targets.values().map(|t| t.out.clone()).collect();
+        let mut tasks = Vec::new();
+        let tids: Vec<_> = self.targets.keys().copied().collect();
+        for tid in tids {
+            let target = self.targets.get_mut(&tid).expect("collected above");
+            let span = Span::of(&target.out, &outs);
+            if span == target.span {
+                continue;
+            }
+            target.span = span;
+            let file = target.file.clone();
+            tasks.push(self.request(&tid, file, Swap::Now));
+        }
+        Task::batch(tasks)
+    }
+
+    /// Pick up `path` for `tid`, animating towards it once it's decoded. A
+    /// transition still running is finished first.
+    fn begin_transition(
+        &mut self,
+        ctl: &mut OrbitCtl<'_>,
+        tid: &TargetId,
+        path: PathBuf,
+    ) -> Task<Msg> {
+        let Some(target) = self.targets.get_mut(tid) else {
+            return Task::None;
+        };
+        Self::finish_transition(ctl, Some(&mut *target));
+        if path == target.file {
+            return Task::None;
+        }
+        self.request(tid, path, Swap::Transition)
+    }
+
+    /// Publish the incoming image and drop the lease on the old one.
+    fn finish_transition(ctl: &mut OrbitCtl<'_>, target: Option<&mut PerTarget>) {
+        let Some(target) = target else {
+            return;
+        };
+        let Some(incoming) = target.incoming.take() else {
+            return;
+        };
+        if let Some(lease) = Self::place_tex(ctl, &target.out, &incoming.file, incoming.tex) {
+            target.tex = Some(lease);
+            target.file = incoming.file;
+            target.animation = incoming.animation;
+            target.player = None;
+            Self::place_palette(ctl, &target.out, incoming.palette);
+        }
+    }
+
+    /// Let the animation on `tid` advance by a frame, starting its player
+    /// if there is none (or it stopped, e.g. while the module was hidden).
+    fn pump_animation(&mut self, tid: &TargetId) -> Task<Msg> {
+        let Some(target) = self.targets.get_mut(tid) else {
+            return Task::None;
+        };
+        let Some(format) = target.animation else {
+            return Task::None;
+        };
+        if target.incoming.is_some() {
+            return Task::None;
+        }
+        if let Some(player) = &target.player
+            && !player.demand.try_send(()).is_err_and(|e| e.is_closed())
+        {
+            return Task::None;
+        }
+
+        self.next_load += 1;
+        let id = self.next_load;
+        let (demand, rx) = async_channel::bounded(1);
+        let _ = demand.try_send(());
+        let player = animation::Player {
+            id,
+            req: Self::decode_request(&self.cfg, target, target.file.clone()),
+            format,
+            min_interval: Duration::from_secs(1) / self.cfg.max_fps.max(1),
+            demand: rx,
+            frames: self.frames.tx.clone(),
+        };
+        target.player = Some(PlayerHandle {
+            id,
+            size: target.size,
+            demand,
+        });
+        Task::blocking(move || player.run())
+    }
+
+    /// (Re)start watching the configured sources. Changes come back as
+    /// `Msg::SourceChanged` through a stream subscription.
+    fn watch(&mut self) {
+        self.watcher = None;
+        let tx = self.changes.tx.clone();
+        self.watcher = source::watch(&self.cfg.roots(), move |paths| {
+            let _ = tx.try_send(paths);
+        });
+    }
+
+    /// Move every output on to its next image.
+    fn cycle(&mut self, ctl: &mut OrbitCtl<'_>) -> Task<Msg> {
+        if self.cfg.mode != Mode::PerOutput {
+            return self.cycle_shared(ctl);
+        }
+        let tids: Vec<_> = self.targets.keys().copied().collect();
+        Task::batch(
+            tids.iter()
+                .map(|tid| self.cycle_target(ctl, tid))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Pick the next image for `tid` from its own source.
+    fn cycle_target(&mut self, ctl: &mut OrbitCtl<'_>, tid: &TargetId) -> Task<Msg> {
+        let Some(target) = self.targets.get(tid) else {
+            return Task::None;
+        };
+        let root = self.source_for(&target.out);
+        match self.pick_image(&root) {
+            Some(path) => self.begin_transition(ctl, tid, path),
+            None if self.index.is_pending(&root) => {
+                self.deferred.insert(root);
+                self.scans()
+            }
+            None => Task::None,
+        }
+    }
+
+    /// Pick the next image shown on every output in `same` and `span` mode.
+    fn cycle_shared(&mut self, ctl: &mut OrbitCtl<'_>) -> Task<Msg> {
+        let root = self.source();
+        let picked = self.pick_image(&root);
+        if picked.is_none() && self.index.is_pending(&root) {
+            self.deferred.insert(root);
+            return self.scans();
+        }
+        self.shared = picked.or(self.shared.take());
+        let Some(path) = self.shared.clone() else {
+            return Task::None;
+        };
+        let tids: Vec<_> = self.targets.keys().copied().collect();
+        Task::batch(
+            tids.iter()
+                .map(|tid| self.begin_transition(ctl, tid, path.clone()))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Move every output showing images from `root` on to its next one.
+    fn cycle_root(&mut self, ctl: &mut OrbitCtl<'_>, root: &Path) -> Task<Msg> {
+        if self.cfg.mode != Mode::PerOutput {
+            return if self.source() == root {
+                self.cycle_shared(ctl)
+            } else {
+                Task::None
+            };
+        }
+        let tids: Vec<_> = self
+            .targets
+            .iter()
+            .filter(|(_, t)| self.source_for(&t.out) == root)
+            .map(|(tid, _)| *tid)
+            .collect();
+        Task::batch(
+            tids.iter()
+                .map(|tid| self.cycle_target(ctl, tid))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Move on from images that were deleted or renamed while shown.
+    fn replace_missing(&mut self, ctl: &mut OrbitCtl<'_>) -> Task<Msg> {
+        if self.cfg.mode != Mode::PerOutput {
+            let gone = self.shared.as_ref().is_some_and(|p| !p.exists());
+            return if gone {
+                self.cycle_shared(ctl)
+            } else {
+                Task::None
+            };
+        }
+        let gone: Vec<_> = self
+            .targets
+            .iter()
+            .filter(|(_, t)| !t.file.exists())
+            .map(|(tid, _)| *tid)
+            .collect();
+        Task::batch(
+            gone.iter()
+                .map(|tid| self.cycle_target(ctl, tid))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn load_overlay_images(&mut self, engine: &mut Engine<'_>) {
+        self.unload_overlay_images(engine);
+        let mut images = HashMap::new();
+        for placed in &self.cfg.widgets {
+            placed.widget.walk(&mut |w| {
+                let WidgetKind::Image {
+                    path,
+                    width,
+                    height,
+                } = &w.kind
+                else {
+                    return;
+                };
+                let key = (path.clone(), *width, *height);
+                if images.contains_key(&key) {
+                    return;
+                }
+                let Ok(img) = image::ImageReader::open(path)
+                    .and_then(|r| r.with_guessed_format())
+                    .map_err(image::ImageError::IoError)
+                    .and_then(|r| r.decode())
+                else {
+                    return;
+                };
+                let img = match (width, height) {
+                    (None, None) => img,
+                    (w, h) => img.resize(
+                        w.unwrap_or(u32::MAX),
+                        h.unwrap_or(u32::MAX),
+                        self.cfg.filter.into(),
+                    ),
+                };
+                let rgba = img.to_rgba8();
+                let tex = engine.load_texture_rgba8(rgba.width(), rgba.height(), &rgba);
+                images.insert(key, tex);
+            });
+        }
+        self.overlay_images = Some(images);
+    }
+
+    fn unload_overlay_images(&mut self, engine: &mut Engine<'_>) {
+        for (_, tex) in self.overlay_images.take().into_iter().flatten() {
+            engine.unload_texture(tex);
+        }
     }
 
     fn get_min_clock_duration(&self) -> Duration {
@@ -180,36 +673,121 @@ impl OrbitModule for Wallpaper {
     type Config = Config;
     type Message = Msg;
 
-    fn cleanup<'a>(&mut self, _engine: &mut Engine<'a>) {
-        self.targets.clear();
+    fn cleanup<'a>(&mut self, engine: &mut Engine<'a>) {
+        // Whatever changed while hidden is picked up by scanning again.
+        self.index.clear();
+        self.waiting.clear();
+        self.deferred.clear();
+        self.watcher = None;
+        self.unload_overlay_images(engine);
+        for (_, target) in self.targets.drain() {
+            if let Some(incoming) = target.incoming {
+                engine.unload_texture(incoming.tex);
+            }
+        }
     }
 
     fn validate_config(cfg: Self::Config) -> Result<(), String> {
         fn validate_widget(widget: &WidgetConfig, errors: &mut Vec<String>) {
-            match widget {
-                WidgetConfig::Clock {
+            for (name, color) in [
+                ("color", &widget.style.color),
+                ("background", &widget.style.background),
+            ] {
+                if let Some(color) = color
+                    && parse_hex_color(color).is_none()
+                {
+                    errors.push(format!(
+                        "- invalid {name} `{color}`, expected #rrggbb or #rrggbbaa"
+                    ));
+                }
+            }
+            if widget.style.padding < 0 {
+                errors.push("- padding must be >= 0".into());
+            }
+            match &widget.kind {
+                WidgetKind::Clock {
                     font_size,
-                    time_format,
+                    time_format: format,
                     ..
+                }
+                | WidgetKind::Date {
+                    font_size, format, ..
                 } => {
                     if *font_size <= 0.0 {
                         errors.push("- font_size must be > 0".into());
                     }
-                    if let Err(e) = chrono::format::StrftimeItems::new(time_format).parse() {
-                        errors.push(format!("- invalid time_format `{time_format}`: {e}"));
+                    if let Err(e) = chrono::format::StrftimeItems::new(format).parse() {
+                        errors.push(format!("- invalid time format `{format}`: {e}"));
+                    }
+                }
+                WidgetKind::Text { font_size, .. } => {
+                    if *font_size <= 0.0 {
+                        errors.push("- font_size must be > 0".into());
                     }
                 }
-                WidgetConfig::Column { children, .. } | WidgetConfig::Row { children, .. } => {
-                    for child in children {
-                        validate_widget(child, errors);
+                WidgetKind::Command {
+                    font_size,
+                    interval,
+                    ..
+                } => {
+                    if *font_size <= 0.0 {
+                        errors.push("- font_size must be > 0".into());
+                    }
+                    match interval.parse::<humantime::Duration>() {
+                        Ok(d) if Duration::from(d).is_zero() => {
+                            errors.push("- command interval must be > 0".into());
+                        }
+                        Ok(_) => {}
+                        Err(e) => errors.push(format!("- invalid interval `{interval}`: {e}")),
                     }
                 }
+                WidgetKind::Image { width, height, .. } => {
+                    if *width == Some(0) || *height == Some(0) {
+                        errors.push("- image width and height must be > 0".into());
+                    }
+                }
+                WidgetKind::Column { .. } | WidgetKind::Row { .. } => {}
             }
         }
 
         let mut errors = Vec::new();
-        for widget in cfg.widgets.into_iter().map(|p| p.widget) {
-            validate_widget(&widget, &mut errors);
+        if parse_hex_color(&cfg.background).is_none() {
+            errors.push(format!(
+                "- invalid background `{}`, expected #rrggbb or #rrggbbaa",
+                cfg.background
+            ));
+        }
+        if cfg.max_fps == 0 {
+            errors.push("- max_fps must be > 0".into());
+        }
+        if let Err(e) = cfg.transition.duration.parse::<humantime::Duration>() {
+            errors.push(format!(
+                "- invalid transition.duration `{}`: {e}",
+                cfg.transition.duration
+            ));
+        }
+        for entry in &cfg.schedule {
+            match schedule::parse_at(&entry.at) {
+                Ok(schedule::At::Time(_)) => {}
+                Ok(_) if cfg.location.is_none() => errors.push(format!(
+                    "- schedule entry `{}` needs a location for sunrise and sunset",
+                    entry.at
+                )),
+                Ok(_) => {}
+                Err(e) => errors.push(format!("- invalid schedule time `{}`: {e}", entry.at)),
+            }
+        }
+        if let Some(loc) = &cfg.location
+            && (!(-90.0..=90.0).contains(&loc.latitude)
+                || !(-180.0..=180.0).contains(&loc.longitude))
+        {
+            errors.push("- location latitude must be within ±90 and longitude within ±180".into());
+        }
+        for placed in &cfg.widgets {
+            if placed.margin < 0 {
+                errors.push("- margin must be >= 0".into());
+            }
+            placed.widget.walk(&mut |w| validate_widget(w, &mut errors));
         }
 
         if errors.is_empty() {
@@ -225,14 +803,31 @@ impl OrbitModule for Wallpaper {
         config: Self::Config,
         _options: &mut orbit_api::ui::sctk::Options,
     ) -> bool {
-        if self.cfg.source != config.source {
+        let scheduled = Self::scheduled_now(&config);
+        let reload = self.cfg.source != config.source
+            || self.scheduled != scheduled
+            || self.cfg.outputs != config.outputs
+            || self.cfg.mode != config.mode
+            || self.cfg.fit != config.fit
+            || self.cfg.filter != config.filter
+            || self.cfg.background != config.background;
+        if reload {
             self.cleanup(engine);
+            self.shared = None;
         }
+        let rewatch = self.cfg.roots() != config.roots();
         self.cfg = config;
+        self.scheduled = scheduled;
+        if rewatch {
+            self.watch();
+        }
 
         self.widgets.clock = self.cfg.widgets.iter().any(|p| p.widget.contains_clock());
+        self.command_outputs.clear();
+        self.load_overlay_images(engine);
 
-        false
+        // Rebuilt surfaces report their size again, which loads new images.
+        reload
     }
 
     fn update<'a>(
@@ -242,6 +837,10 @@ impl OrbitModule for Wallpaper {
         engine: &mut Engine<'a>,
         event: &Event<Self::Message>,
     ) -> Task<Msg> {
+        if self.overlay_images.is_none() {
+            self.load_overlay_images(engine);
+        }
+
         match event {
             &Event::Resized { size } => {
                 let Some(tid) = tid else {
@@ -251,21 +850,21 @@ impl OrbitModule for Wallpaper {
                     return Task::None;
                 };
 
-                if let Some(target) = self.targets.get_mut(&tid) {
-                    if target.size == size {
-                        return Task::None;
-                    }
-                    if let Some(tex) =
-                        Self::load_texture(&target.file, size.width, size.height, engine)
-                        && let Some(lease) = Self::place_tex(ctl, &out, tex)
-                    {
-                        target.tex = lease;
-                        target.size = size;
-                    }
-                } else {
-                    self.ensure_texture_loaded(ctl, &tid, out, engine);
+                let Some(target) = self.targets.get_mut(&tid) else {
+                    return self.add_target(&tid, out, engine);
+                };
+                target.out = out;
+                if target.size == size {
+                    return self.respan();
                 }
-                Task::RedrawTarget
+                Self::finish_transition(ctl, Some(&mut *target));
+                target.size = size;
+                let file = target.file.clone();
+                Task::batch([
+                    self.respan(),
+                    self.request(&tid, file, Swap::Now),
+                    Task::RedrawTarget,
+                ])
             }
             Event::Message(Msg::Tick) => {
                 if self.widgets.clock {
@@ -274,13 +873,119 @@ impl OrbitModule for Wallpaper {
                     Task::None
                 }
             }
-            Event::Message(Msg::Cycle) => {
-                let targets_to_reload: Vec<_> = self.targets.drain().collect();
-                for (tid, target) in targets_to_reload {
-                    self.ensure_texture_loaded(ctl, &tid, target.out, engine);
+            Event::RedrawRequested => {
+                let Some(tid) = tid else {
+                    return Task::None;
+                };
+                let Some(target) = self.targets.get_mut(&tid) else {
+                    return Task::None;
+                };
+                let task = match &target.incoming {
+                    Some(incoming) if incoming.progress() >= 1.0 => {
+                        Self::finish_transition(ctl, Some(target));
+                        Task::RedrawTarget
+                    }
+                    Some(_) => Task::RedrawTarget,
+                    None => Task::None,
+                };
+                if self.locked {
+                    return task;
+                }
+                Task::batch([task, self.pump_animation(&tid)])
+            }
+            Event::Message(Msg::Schedule) => {
+                let scheduled = Self::scheduled_now(&self.cfg);
+                if scheduled == self.scheduled {
+                    return Task::None;
                 }
+                self.scheduled = scheduled;
+                self.cycle(ctl)
+            }
+            Event::Message(Msg::Cycle) => self.cycle(ctl),
+            Event::Message(Msg::Watch) => {
+                self.watch();
+                Task::None
+            }
+            Event::Message(Msg::SourceChanged(changes)) => {
+                self.index.update(changes);
+                self.replace_missing(ctl)
+            }
+            Event::Message(Msg::CommandOutput(command, stdout)) => {
+                if self.command_outputs.get(command) == Some(stdout) {
+                    return Task::None;
+                }
+                self.command_outputs.insert(command.clone(), stdout.clone());
                 Task::RedrawModule
             }
+            Event::Message(Msg::Rescan) => {
+                self.index.clear();
+                self.watch();
+                self.replace_missing(ctl)
+            }
+            Event::Message(Msg::Indexed(epoch, root, found)) => {
+                if !self.index.insert(*epoch, root, HashSet::clone(found)) {
+                    return Task::None;
+                }
+                // Cycled first, so outputs added below don't move on right away.
+                let mut tasks = Vec::new();
+                if self.deferred.remove(root) {
+                    tasks.push(self.cycle_root(ctl, root));
+                }
+                for (tid, out) in std::mem::take(&mut self.waiting) {
+                    tasks.push(self.add_target(&tid, out, engine));
+                }
+                Task::batch(tasks)
+            }
+            Event::Message(Msg::Decoded(id, decoded)) => {
+                if self.finish_load(ctl, *id, decoded.as_deref(), engine) {
+                    Task::RedrawModule
+                } else {
+                    Task::None
+                }
+            }
+            Event::Message(Msg::Frame(id, rgba)) => {
+                let Some((&owner, target)) = self
+                    .targets
+                    .iter_mut()
+                    .find(|(_, t)| t.player.as_ref().is_some_and(|p| p.id == *id))
+                else {
+                    return Task::None;
+                };
+                let Some(player) = &target.player else {
+                    return Task::None;
+                };
+                let (w, h) = (player.size.width, player.size.height);
+                // The engine can't write into a texture, so this is one upload
+                // per frame. Published like a still, so readers follow along;
+                // the frame it replaces is reclaimed once they let go of it.
+                let tex = engine.load_texture_rgba8(w, h, rgba);
+                if let Some(lease) = Self::place_tex(ctl, &target.out, &target.file, tex) {
+                    target.tex = Some(lease);
+                }
+                // Redraw just the owner when we can, so its frame callbacks
+                // keep pacing the player.
+                if tid == Some(owner) {
+                    Task::RedrawTarget
+                } else {
+                    Task::RedrawModule
+                }
+            }
+            &Event::Message(Msg::Stopped(id)) => {
+                for target in self.targets.values_mut() {
+                    if target.player.as_ref().is_some_and(|p| p.id == id) {
+                        target.player = None;
+                    }
+                }
+                Task::None
+            }
+            &Event::Message(Msg::Locked(locked)) => {
+                self.locked = locked;
+                if locked {
+                    Task::None
+                } else {
+                    Task::RedrawModule
+                }
+            }
             _ => Task::None,
         }
     }
@@ -290,14 +995,29 @@ impl OrbitModule for Wallpaper {
             return Rectangle::placeholder().into();
         };
 
-        let mut view = Overlay::new(el![
-            Image::new(Size::splat(Length::Grow), *target.tex).fit(ContentFit::Cover)
-        ])
-        .size(Size::splat(Length::Grow));
+        let image: Element = match (target.tex.as_deref().copied(), &target.incoming) {
+            (Some(tex), Some(incoming)) => {
+                TransitionImage::new(Size::splat(Length::Grow), tex, incoming.tex)
+                    .kind(self.cfg.transition.kind)
+                    .progress(incoming.progress())
+                    .into()
+            }
+            (Some(tex), None) => Image::new(Size::splat(Length::Grow), tex)
+                .fit(ContentFit::Cover)
+                .into(),
+            (None, _) => Rectangle::placeholder().into(),
+        };
+        let mut view = Overlay::new(el![image]).size(Size::splat(Length::Grow));
 
-        let now = chrono::Local::now();
+        let no_images = HashMap::new();
+        let cx = overlay::Context {
+            now: &chrono::Local::now(),
+            target,
+            images: self.overlay_images.as_ref().unwrap_or(&no_images),
+            outputs: &self.command_outputs,
+        };
         for widget in self.cfg.widgets.iter() {
-            widget.place(target, &now, &mut view);
+            widget.place(&cx, &mut view);
         }
 
         view.into()
@@ -310,10 +1030,62 @@ impl OrbitModule for Wallpaper {
             .parse::<humantime::Duration>()
             .map(Into::into)
             .unwrap_or(Duration::from_secs(3600));
-        let mut subs = vec![Subscription::Interval {
-            every: cycle,
-            message: Msg::Cycle,
-        }];
+        let frames = self.frames.rx.clone();
+        let changes = self.changes.rx.clone();
+        let mut subs = vec![
+            Subscription::Interval {
+                every: cycle,
+                message: Msg::Cycle,
+            },
+            Subscription::topic(SESSION_LOCKED, Msg::Locked),
+            // Animation frames and source changes come in through these two.
+            // Hiding drops them at whatever they are waiting on.
+            Subscription::stream(move |tx| async move {
+                while let Ok(msg) = frames.recv().await {
+                    if tx.send(msg).is_err() {
+                        return;
+                    }
+                }
+            }),
+            Subscription::stream(move |tx| async move {
+                let _ = tx.send(Msg::Watch);
+                while let Some(found) = source::next_changes(&changes).await {
+                    if tx.send(Msg::SourceChanged(Arc::new(found))).is_err() {
+                        return;
+                    }
+                }
+            }),
+        ];
+
+        let mut commands = HashMap::new();
+        for p in &self.cfg.widgets {
+            p.widget.commands(&mut commands);
+        }
+        for (command, every) in commands {
+            subs.push(overlay::command_subscription(command, every));
+        }
+
+        // A timer armed for each next entry, so outputs switch right at the
+        // boundaries. Not a `SyncedTimeout`: that fires once, on a multiple
+        // of its period, and subscriptions are only collected when shown, so
+        // it couldn't be armed again for the entry after. A `SyncedInterval`
+        // would wake every minute for the few boundaries in a day.
+        if !self.cfg.schedule.is_empty() {
+            let entries = self.cfg.schedule.clone();
+            let location = self.cfg.location.clone();
+            subs.push(Subscription::stream(move |tx| async move {
+                loop {
+                    let now = chrono::Local::now();
+                    let wait = schedule::next_boundary(&entries, location.as_ref(), now)
+                        .and_then(|at| (at - now.naive_local()).to_std().ok())
+                        .map_or(schedule::RECHECK, |wait| wait.min(schedule::RECHECK));
+                    async_io::Timer::after(wait).await;
+                    if tx.send(Msg::Schedule).is_err() {
+                        return;
+                    }
+                }
+            }));
+        }
 
         if self.widgets.clock {
             subs.push(Subscription::SyncedInterval {
@@ -338,6 +1110,7 @@ orbit_plugin! {
         namespace: Some("orbit-wallpaper".to_string()),
         output: Some(OutputSet::All),
     }),
-    commands: [("next", Msg::Cycle)],
+    commands: [("next", Msg::Cycle), ("rescan", Msg::Rescan)],
     show_on_startup: true,
+    pipelines: orbit_api::ui::pipeline_factories!["wallpaper_transition" => pipeline::TransitionPipeline],
 }
@@ -0,0 +1,297 @@
+use std::{collections::HashMap, fs, path::PathBuf, sync::OnceLock, time::Duration};
+
+use async_io::Timer;
+use async_process::{Command, Stdio};
+use chrono::{DateTime, Local};
+use futures_lite::future;
+use orbit_api::{
+    Subscription,
+    ui::{
+        el,
+        model::{Color, Family, Size, Vec4},
+        render::texture::TextureHandle,
+        text::Wrap,
+        widget::{Column, Element, Image, Length, Overlay, Row, Spacer, Text},
+    },
+};
+
+use crate::{
+    Msg, PerTarget,
+    config::{Align, FontFamilyConfig, Placed, WidgetConfig, WidgetKind, parse_hex_color},
+};
+
+/// How long a `command` widget's command gets to print before it is killed.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Overlay images, keyed by path and the size they were scaled to fit.
+pub type ImageKey = (PathBuf, Option<u32>, Option<u32>);
+
+/// What the widgets of one output are drawn from.
+pub struct Context<'a> {
+    pub now: &'a DateTime<Local>,
+    pub target: &'a PerTarget,
+    pub images: &'a HashMap<ImageKey, TextureHandle>,
+    /// Last output of each command.
+    pub outputs: &'a HashMap<String, String>,
+}
+
+impl Placed {
+    pub fn place(&self, cx: &Context, on: &mut Overlay) {
+        let el = self.widget.element(cx);
+        if let Some(anchor) = self.anchor {
+            let (h, v) = anchor.aligns();
+            let row = aligned(el, h, Length::Grow);
+            let col = match v {
+                Align::Start => el![row, vspacer()],
+                Align::Center => el![vspacer(), row, vspacer()],
+                Align::End => el![vspacer(), row],
+            };
+            let anchored: Element = Column::new(col)
+                .size(Size::splat(Length::Grow))
+                .padding(Vec4::splat(self.margin))
+                .into();
+            on.push(anchored, 0, 0);
+            return;
+        }
+
+        let (w, h) = (cx.target.size.width as f32, cx.target.size.height as f32);
+        let x = (w * self.x.clamp(0.0, 1.0)).ceil() as i32;
+        let y = (h * self.y.clamp(0.0, 1.0)).ceil() as i32;
+        match self.widget.style.align {
+            Align::Start => on.push(el, x, y),
+            // Right-aligned to `x`: a row ending there, pushed to its end.
+            Align::End => on.push(aligned(el, Align::End, Length::Fixed(x)), 0, y),
+            // Centered on `x`: a row reaching as far on both sides.
+            Align::Center => {
+                let half = x.min(w as i32 - x).max(0);
+                on.push(
+                    aligned(el, Align::Center, Length::Fixed(half * 2)),
+                    x - half,
+                    y,
+                );
+            }
+        }
+    }
+}
+
+impl WidgetConfig {
+    fn element(&self, cx: &Context) -> Element {
+        let color = self.style.color.as_deref().and_then(parse_hex_color);
+        let el: Element = match &self.kind {
+            WidgetKind::Clock {
+                font_size,
+                font_family,
+                time_format,
+            } => text(
+                cx.now.format(time_format).to_string(),
+                *font_size,
+                Some(font_family.clone().unwrap_or(FontFamilyConfig::Monospace)),
+                color,
+            ),
+            WidgetKind::Date {
+                font_size,
+                font_family,
+                format,
+            } => text(
+                cx.now.format(format).to_string(),
+                *font_size,
+                font_family.clone(),
+                color,
+            ),
+            WidgetKind::Text {
+                text: template,
+                font_size,
+                font_family,
+            } => text(expand(template, cx), *font_size, font_family.clone(), color),
+            WidgetKind::Command {
+                command,
+                font_size,
+                font_family,
+                ..
+            } => text(
+                cx.outputs.get(command).cloned().unwrap_or_default(),
+                *font_size,
+                Some(font_family.clone().unwrap_or(FontFamilyConfig::Monospace)),
+                color,
+            ),
+            WidgetKind::Image {
+                path,
+                width,
+                height,
+            } => match cx.images.get(&(path.clone(), *width, *height)) {
+                Some(&tex) => Image::new(
+                    Size::new(
+                        Length::Fixed(tex.size_px.width as i32),
+                        Length::Fixed(tex.size_px.height as i32),
+                    ),
+                    tex,
+                )
+                .into(),
+                None => Spacer::new(Size::splat(Length::Fixed(0))).into(),
+            },
+            WidgetKind::Column { spacing, children } => {
+                let mut col = Column::empty().spacing(*spacing);
+                for child in children {
+                    let el = child.element(cx);
+                    col.push(match child.style.align {
+                        Align::Start => el,
+                        align => aligned(el, align, Length::Grow),
+                    });
+                }
+                col.into()
+            }
+            WidgetKind::Row { spacing, children } => {
+                let mut row = Row::empty().spacing(*spacing);
+                for child in children {
+                    row.push(child.element(cx));
+                }
+                row.into()
+            }
+        };
+
+        let background = self.style.background.as_deref().and_then(parse_hex_color);
+        if self.style.padding == 0 && background.is_none() {
+            return el;
+        }
+        let [r, g, b, a] = background.unwrap_or([0, 0, 0, 0]);
+        Row::new(el![el])
+            .size(Size::splat(Length::Fit))
+            .padding(Vec4::splat(self.style.padding))
+            .color(Color::rgba(r, g, b, a))
+            .into()
+    }
+}
+
+fn text(
+    s: String,
+    font_size: f32,
+    family: Option<FontFamilyConfig>,
+    color: Option<[u8; 4]>,
+) -> Element {
+    let mut text = Text::new(s)
+        .wrap(Wrap::None)
+        .font_size(font_size)
+        .size(Size::splat(Length::Fit));
+    if let Some(family) = family {
+        text = text.family(Family::from(family));
+    }
+    if let Some([r, g, b, a]) = color {
+        text = text.color(Color::rgba(r, g, b, a));
+    }
+    text.into()
+}
+
+fn vspacer() -> Spacer {
+    Spacer::new(Size::new(Length::Fit, Length::Grow))
+}
+
+/// `el` in a row of `width`, pushed to one side or centered by spacers.
+fn aligned(el: Element, align: Align, width: Length) -> Element {
+    let spacer = || Spacer::new(Size::new(Length::Grow, Length::Fit));
+    let children = match align {
+        Align::Start => el![el, spacer()],
+        Align::Center => el![spacer(), el, spacer()],
+        Align::End => el![spacer(), el],
+    };
+    Row::new(children)
+        .size(Size::new(width, Length::Fit))
+        .into()
+}
+
+/// Fill in the `{...}` placeholders of a `text` widget. Unknown ones are
+/// left as they are.
+fn expand(template: &str, cx: &Context) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let Some(len) = rest[open..].find('}') else {
+            rest = &rest[open..];
+            break;
+        };
+        let key = &rest[open + 1..open + len];
+        match placeholder(key, cx) {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(&rest[open..=open + len]),
+        }
+        rest = &rest[open + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn placeholder(key: &str, cx: &Context) -> Option<String> {
+    static HOST: OnceLock<String> = OnceLock::new();
+    match key {
+        "user" => std::env::var("USER").ok(),
+        "host" => Some(
+            HOST.get_or_init(|| {
+                fs::read_to_string("/proc/sys/kernel/hostname")
+                    .map(|h| h.trim().to_string())
+                    .unwrap_or_default()
+            })
+            .clone(),
+        ),
+        "output" => Some(cx.target.out.name.clone().unwrap_or_default()),
+        "wallpaper" => Some(
+            cx.target
+                .file
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        ),
+        _ => std::env::var(key.strip_prefix("env:")?).ok(),
+    }
+}
+
+/// Runs `command` with `sh -c` every `every`, sending its stdout as
+/// `Msg::CommandOutput`. A run that takes longer than `COMMAND_TIMEOUT` is
+/// killed and shows nothing.
+pub fn command_subscription(command: String, every: Duration) -> Subscription<Msg> {
+    Subscription::stream(move |tx| async move {
+        loop {
+            let timeout = async {
+                Timer::after(COMMAND_TIMEOUT).await;
+                None
+            };
+            let stdout = future::or(command_output(&command), timeout)
+                .await
+                .unwrap_or_default();
+            if tx
+                .send(Msg::CommandOutput(command.clone(), stdout))
+                .is_err()
+            {
+                return;
+            }
+            let closed = async {
+                tx.closed().await;
+                true
+            };
+            let next = async {
+                Timer::after(every).await;
+                false
+            };
+            if future::or(closed, next).await {
+                return;
+            }
+        }
+    })
+}
+
+/// Stdout of `command`, trimmed. Dropping this kills it.
+async fn command_output(command: &str) -> Option<String> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .ok()?;
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string(),
+    )
+}
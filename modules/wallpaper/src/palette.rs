@@ -0,0 +1,75 @@
+use std::{cmp::Reverse, ops::Range};
+
+use orbit_keys::Palette;
+
+/// Colors extracted from each image.
+const COLORS: usize = 6;
+/// Pixels looked at; plenty for a stable result and cheap to sort.
+const SAMPLES: usize = 16384;
+
+/// Median cut over a sample of the RGBA8 `pixels`: the box of colors with
+/// the widest channel range is split at its median on that channel until
+/// there are `COLORS` boxes, and each box contributes its mean color.
+pub fn extract(pixels: &[u8]) -> Palette {
+    let step = (pixels.len() / 4 / SAMPLES).max(1);
+    let mut samples: Vec<[u8; 3]> = pixels
+        .chunks_exact(4)
+        .step_by(step)
+        // Mostly transparent pixels show the background, not the image.
+        .filter(|p| p[3] >= 128)
+        .map(|p| [p[0], p[1], p[2]])
+        .collect();
+
+    let mut boxes: Vec<Range<usize>> = vec![0..samples.len()];
+    while boxes.len() < COLORS {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (i, widest_channel(&samples[r.clone()])))
+            .max_by_key(|&(_, (_, range))| range);
+        let Some((i, (channel, range))) = widest else {
+            break;
+        };
+        if range == 0 {
+            break;
+        }
+        let r = boxes.swap_remove(i);
+        samples[r.clone()].sort_unstable_by_key(|p| p[channel]);
+        let mid = r.start + r.len() / 2;
+        boxes.push(r.start..mid);
+        boxes.push(mid..r.end);
+    }
+
+    boxes.sort_by_key(|r| Reverse(r.len()));
+    Palette {
+        colors: boxes
+            .into_iter()
+            .filter(|r| !r.is_empty())
+            .map(|r| mean(&samples[r]))
+            .collect(),
+    }
+}
+
+/// The channel whose values spread the most in `px`, and that spread.
+fn widest_channel(px: &[[u8; 3]]) -> (usize, u8) {
+    (0..3)
+        .map(|c| {
+            let (lo, hi) = px.iter().fold((u8::MAX, u8::MIN), |(lo, hi), p| {
+                (lo.min(p[c]), hi.max(p[c]))
+            });
+            (c, hi.saturating_sub(lo))
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap_or((0, 0))
+}
+
+fn mean(px: &[[u8; 3]]) -> [u8; 3] {
+    let mut sum = [0u64; 3];
+    for p in px {
+        for (s, v) in sum.iter_mut().zip(p) {
+            *s += u64::from(*v);
+        }
+    }
+    let n = px.len().max(1) as u64;
+    sum.map(|s| (s / n) as u8)
+}
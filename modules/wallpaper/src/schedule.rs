@@ -0,0 +1,141 @@
+use std::{f64::consts::PI, path::Path, time::Duration};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+
+use crate::config::{Location, ScheduleEntry};
+
+/// Longest wait for the next entry before checking again. Timers stand
+/// still while the machine is suspended, so this bounds how late a switch
+/// can be after waking up.
+pub const RECHECK: Duration = Duration::from_secs(600);
+
+/// When a schedule entry starts, parsed from its `at`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum At {
+    Time(NaiveTime),
+    /// Seconds after (or before, if negative) sunrise.
+    Sunrise(i64),
+    Sunset(i64),
+}
+
+/// `HH:MM`, `sunrise` or `sunset`, the latter optionally shifted like
+/// `sunset-30m` or `sunrise+1h`.
+pub fn parse_at(s: &str) -> Result<At, String> {
+    let s = s.trim();
+    let (sunrise, rest) = if let Some(rest) = s.strip_prefix("sunrise") {
+        (true, rest)
+    } else if let Some(rest) = s.strip_prefix("sunset") {
+        (false, rest)
+    } else {
+        return NaiveTime::parse_from_str(s, "%H:%M")
+            .map(At::Time)
+            .map_err(|_| "expected HH:MM, sunrise or sunset".to_string());
+    };
+
+    let rest = rest.trim_start();
+    let (sign, offset) = match rest.chars().next() {
+        None => (0, "0s"),
+        Some('+') => (1, &rest[1..]),
+        Some('-') => (-1, &rest[1..]),
+        Some(_) => return Err("expected + or - after sunrise/sunset".into()),
+    };
+    let offset: Duration = offset
+        .trim()
+        .parse::<humantime::Duration>()
+        .map_err(|e| e.to_string())?
+        .into();
+    let offset = sign * offset.as_secs() as i64;
+    Ok(if sunrise {
+        At::Sunrise(offset)
+    } else {
+        At::Sunset(offset)
+    })
+}
+
+/// Local time `at` falls on for `day`. `None` if it can't be parsed, or the
+/// sun doesn't rise or set that day.
+fn resolve(at: &str, day: NaiveDate, location: Option<&Location>) -> Option<NaiveDateTime> {
+    let (event, offset) = match parse_at(at).ok()? {
+        At::Time(t) => return Some(day.and_time(t)),
+        At::Sunrise(offset) => (sun_times(day, location?)?.0, offset),
+        At::Sunset(offset) => (sun_times(day, location?)?.1, offset),
+    };
+    Some((event + TimeDelta::seconds(offset)).naive_local())
+}
+
+/// The source of the entry that started last before `now`, carrying over
+/// from the day before until today's first entry.
+pub fn active<'a>(
+    entries: &'a [ScheduleEntry],
+    location: Option<&Location>,
+    now: DateTime<Local>,
+) -> Option<&'a Path> {
+    let on = |day: NaiveDate| {
+        entries
+            .iter()
+            .filter_map(move |e| Some((resolve(&e.at, day, location)?, e)))
+    };
+    let today = now.date_naive();
+    let now = now.naive_local();
+    on(today)
+        .filter(|(start, _)| *start <= now)
+        .max_by_key(|(start, _)| *start)
+        .or_else(|| on(today.pred_opt()?).max_by_key(|(start, _)| *start))
+        .map(|(_, e)| e.source.as_path())
+}
+
+/// When the next entry after `now` starts, today or tomorrow. `None` if
+/// none does, e.g. sun-based entries during polar day or night.
+pub fn next_boundary(
+    entries: &[ScheduleEntry],
+    location: Option<&Location>,
+    now: DateTime<Local>,
+) -> Option<NaiveDateTime> {
+    let today = now.date_naive();
+    let now = now.naive_local();
+    [Some(today), today.succ_opt()]
+        .into_iter()
+        .flatten()
+        .flat_map(|day| {
+            entries
+                .iter()
+                .filter_map(move |e| resolve(&e.at, day, location))
+        })
+        .filter(|start| *start > now)
+        .min()
+}
+
+/// Sunrise and sunset on `day` at `location`, with the sunrise equation.
+/// `None` during polar day or night.
+fn sun_times(day: NaiveDate, location: &Location) -> Option<(DateTime<Local>, DateTime<Local>)> {
+    let rad = PI / 180.0;
+    // Julian day at midnight UTC.
+    let unix_days = day
+        .signed_duration_since(NaiveDate::from_ymd_opt(1970, 1, 1)?)
+        .num_days();
+    let jd = unix_days as f64 + 2440587.5;
+    let n = (jd - 2451545.0 + 0.0008).ceil();
+
+    let mean_noon = n - location.longitude / 360.0;
+    let m = (357.5291 + 0.98560028 * mean_noon).rem_euclid(360.0);
+    let c =
+        1.9148 * (m * rad).sin() + 0.02 * (2.0 * m * rad).sin() + 0.0003 * (3.0 * m * rad).sin();
+    let lambda = (m + c + 180.0 + 102.9372).rem_euclid(360.0);
+    let transit =
+        2451545.0 + mean_noon + 0.0053 * (m * rad).sin() - 0.0069 * (2.0 * lambda * rad).sin();
+
+    let sin_decl = (lambda * rad).sin() * (23.4397 * rad).sin();
+    let cos_decl = sin_decl.asin().cos();
+    let lat = location.latitude * rad;
+    let cos_hour = ((-0.833 * rad).sin() - lat.sin() * sin_decl) / (lat.cos() * cos_decl);
+    if !(-1.0..=1.0).contains(&cos_hour) {
+        return None;
+    }
+    let half_day = cos_hour.acos() / rad / 360.0;
+
+    let at = |julian: f64| {
+        let secs = ((julian - 2440587.5) * 86400.0).round() as i64;
+        DateTime::from_timestamp(secs, 0).map(|t| t.with_timezone(&Local))
+    };
+    Some((at(transit - half_day)?, at(transit + half_day)?))
+}
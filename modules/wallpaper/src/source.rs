@@ -0,0 +1,204 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use async_channel::Receiver;
+use async_io::Timer;
+use notify::{
+    EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+    event::{AccessKind, AccessMode, ModifyKind},
+};
+
+use crate::{config::Order, format};
+
+/// How long a burst of changes, like a copy or an unpacked archive, gets to
+/// settle into one batch.
+const SETTLE: Duration = Duration::from_millis(250);
+
+/// Paths changed on disk, each with the images now found under it.
+pub type Changes = Vec<(PathBuf, HashSet<PathBuf>)>;
+
+fn is_supported(p: &Path) -> bool {
+    format::sniff_file(p).is_some()
+}
+
+fn collect_images_rec(path: &Path, out: &mut Vec<PathBuf>) {
+    if path.is_file() {
+        if is_supported(path) {
+            out.push(path.to_path_buf());
+        }
+        return;
+    }
+
+    let Ok(rd) = fs::read_dir(path) else {
+        return;
+    };
+    for e in rd.flatten() {
+        let p = e.path();
+        if p.is_dir() {
+            collect_images_rec(&p, out);
+        } else if p.is_file() && is_supported(&p) {
+            out.push(p);
+        }
+    }
+}
+
+/// Every image under `root`, or `root` itself if it is one. Reads the start
+/// of every file to tell its format, so this runs on a worker.
+pub fn scan(root: &Path) -> HashSet<PathBuf> {
+    let mut all = Vec::new();
+    collect_images_rec(root, &mut all);
+    all.into_iter().collect()
+}
+
+/// Images found under each source, kept current by a [`watch`]er so
+/// picking one doesn't walk the tree every time.
+#[derive(Default)]
+pub struct Index {
+    roots: HashMap<PathBuf, HashSet<PathBuf>>,
+    /// Roots asked for but not indexed yet, and whether their scan started.
+    pending: HashMap<PathBuf, bool>,
+    /// Bumped by [`Index::clear`], so scans started before are dropped.
+    epoch: u64,
+}
+
+impl Index {
+    /// Every image under `root`, sorted for `order`. `None` until `root` was
+    /// [`scan`]ned: it is queued for the next [`Index::start_scans`].
+    pub fn images(&mut self, root: &Path, order: Order) -> Option<Vec<PathBuf>> {
+        let Some(found) = self.roots.get(root) else {
+            self.pending.entry(root.to_path_buf()).or_insert(false);
+            return None;
+        };
+        Some(sorted(found.iter().cloned().collect(), order))
+    }
+
+    pub fn is_pending(&self, root: &Path) -> bool {
+        self.pending.contains_key(root)
+    }
+
+    /// The queued roots to scan now, with the epoch to hand back to
+    /// [`Index::insert`].
+    pub fn start_scans(&mut self) -> Vec<(u64, PathBuf)> {
+        self.pending
+            .iter_mut()
+            .filter(|(_, started)| !**started)
+            .map(|(root, started)| {
+                *started = true;
+                (self.epoch, root.clone())
+            })
+            .collect()
+    }
+
+    /// Take in the scan of `root`. `false` if it is stale.
+    pub fn insert(&mut self, epoch: u64, root: &Path, found: HashSet<PathBuf>) -> bool {
+        if epoch != self.epoch || self.pending.remove(root).is_none() {
+            return false;
+        }
+        self.roots.insert(root.to_path_buf(), found);
+        true
+    }
+
+    /// Take in what [`next_changes`] found. Removed directories take their
+    /// images with them.
+    pub fn update(&mut self, changes: &[(PathBuf, HashSet<PathBuf>)]) {
+        for (root, found) in &mut self.roots {
+            for (path, images) in changes.iter().filter(|(p, _)| p.starts_with(root)) {
+                found.retain(|p| !p.starts_with(path));
+                found.extend(images.iter().cloned());
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.roots.clear();
+        self.pending.clear();
+        self.epoch += 1;
+    }
+}
+
+fn sorted(mut all: Vec<PathBuf>, order: Order) -> Vec<PathBuf> {
+    match order {
+        Order::Random => {}
+        Order::Sequential => all.sort(),
+        Order::Name => all.sort_by(|a, b| a.file_name().cmp(&b.file_name()).then_with(|| a.cmp(b))),
+        Order::Mtime => all.sort_by_cached_key(|p| {
+            let mtime = fs::metadata(p).and_then(|m| m.modified());
+            (mtime.unwrap_or(SystemTime::UNIX_EPOCH), p.clone())
+        }),
+    }
+    all
+}
+
+/// Report paths created, changed or removed under `roots` to `on_change`
+/// until the returned watcher is dropped. Single-file roots are watched
+/// through their directory, so replacing the file is noticed too.
+pub fn watch<F>(roots: &[PathBuf], on_change: F) -> Option<RecommendedWatcher>
+where
+    F: Fn(Vec<PathBuf>) + Send + 'static,
+{
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(ev) = res else {
+            return;
+        };
+        // A file being written is looked at once, when it is closed. Reads
+        // (including our own) only show up as opens and closes.
+        let changed = matches!(
+            ev.kind,
+            EventKind::Create(_)
+                | EventKind::Modify(ModifyKind::Name(_))
+                | EventKind::Remove(_)
+                | EventKind::Access(AccessKind::Close(AccessMode::Write))
+        );
+        if changed && !ev.paths.is_empty() {
+            on_change(ev.paths);
+        }
+    })
+    .ok()?;
+
+    for root in roots {
+        let watched = if root.is_dir() {
+            watcher.watch(root, RecursiveMode::Recursive)
+        } else if let Some(dir) = root.parent() {
+            watcher.watch(dir, RecursiveMode::NonRecursive)
+        } else {
+            continue;
+        };
+        // Sources that don't exist yet are skipped; `rescan` watches again.
+        let _ = watched;
+    }
+    Some(watcher)
+}
+
+/// Wait for the next paths reported by [`watch`], give the burst they start
+/// time to settle, then scan each distinct path once on the blocking pool.
+/// `None` once nothing can report anymore.
+pub async fn next_changes(rx: &Receiver<Vec<PathBuf>>) -> Option<Changes> {
+    let mut paths: HashSet<PathBuf> = rx.recv().await.ok()?.into_iter().collect();
+    Timer::after(SETTLE).await;
+    while let Ok(more) = rx.try_recv() {
+        paths.extend(more);
+    }
+    let changes = orbit_api::unblock(move || {
+        paths
+            .into_iter()
+            .map(|path| {
+                let found = scan(&path);
+                (path, found)
+            })
+            .collect()
+    });
+    Some(changes.await)
+}
+
+/// The image following `last` in `all`, wrapping around. Starts over from
+/// the first one when `last` is unset or gone.
+pub fn next_after(all: &[PathBuf], last: Option<&Path>) -> Option<PathBuf> {
+    let i = last
+        .and_then(|last| all.iter().position(|p| p == last))
+        .map_or(0, |i| (i + 1) % all.len());
+    all.get(i).cloned()
+}
@@ -0,0 +1,64 @@
+use image::DynamicImage;
+use orbit_api::OutputInfo;
+
+/// Where one output sits in the layout of all outputs, as logical
+/// `[x, y, w, h]` rects.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    bounds: [i32; 4],
+    rect: [i32; 4],
+}
+
+fn logical_rect(out: &OutputInfo) -> Option<[i32; 4]> {
+    let (x, y) = out.logical_position?;
+    let (w, h) = out.logical_size?;
+    (w > 0 && h > 0).then_some([x, y, w, h])
+}
+
+impl Span {
+    /// `out`'s place among `all`. `None` when an output doesn't report its
+    /// logical geometry, in which case it gets the whole image instead.
+    pub fn of<'a>(out: &OutputInfo, all: impl IntoIterator<Item = &'a OutputInfo>) -> Option<Self> {
+        let rect = logical_rect(out)?;
+        let [mut x0, mut y0] = [rect[0], rect[1]];
+        let [mut x1, mut y1] = [rect[0] + rect[2], rect[1] + rect[3]];
+        for other in all {
+            let [x, y, w, h] = logical_rect(other)?;
+            x0 = x0.min(x);
+            y0 = y0.min(y);
+            x1 = x1.max(x + w);
+            y1 = y1.max(y + h);
+        }
+        Some(Self {
+            bounds: [x0, y0, x1 - x0, y1 - y0],
+            rect,
+        })
+    }
+
+    /// How much to scale a `src_w` x `src_h` image so that, once cropped,
+    /// this output's slice is `width` pixels wide.
+    pub fn cover_scale(&self, src_w: f32, src_h: f32, width: u32) -> f32 {
+        let density = width as f32 / self.rect[2] as f32;
+        (self.bounds[2] as f32 * density / src_w).max(self.bounds[3] as f32 * density / src_h)
+    }
+
+    /// Cover-fit `img` to the whole layout and cut out this output's part.
+    pub fn crop(&self, img: &DynamicImage) -> DynamicImage {
+        let [bx, by, bw, bh] = self.bounds.map(|v| v as f32);
+        let [rx, ry, rw, rh] = self.rect.map(|v| v as f32);
+        let (iw, ih) = (img.width() as f32, img.height() as f32);
+
+        // Image pixels per logical pixel, and the part of the image the
+        // layout covers.
+        let s = (iw / bw).min(ih / bh);
+        let cx = (iw - bw * s) * 0.5;
+        let cy = (ih - bh * s) * 0.5;
+
+        img.crop_imm(
+            (cx + (rx - bx) * s).round() as u32,
+            (cy + (ry - by) * s).round() as u32,
+            ((rw * s).round() as u32).max(1),
+            ((rh * s).round() as u32).max(1),
+        )
+    }
+}
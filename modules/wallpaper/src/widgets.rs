@@ -0,0 +1,185 @@
+use orbit_api::ui::{
+    context::{LayoutCtx, PaintCtx},
+    layout::Node,
+    model::*,
+    primitive::Instance,
+    render::{pipeline::PipelineKey, texture::TextureHandle},
+    widget::{IntoElement, Length, Widget},
+};
+
+use crate::config::TransitionKind;
+
+/// Two wallpapers mid-transition, both cover-fitted to the widget's rect.
+/// `progress` runs from 0.0 (only `from` visible) to 1.0 (only `to`).
+pub struct TransitionImage {
+    size: Size<Length>,
+    from: TextureHandle,
+    to: TextureHandle,
+    kind: TransitionKind,
+    progress: f32,
+}
+
+impl TransitionImage {
+    pub fn new(size: Size<Length>, from: TextureHandle, to: TextureHandle) -> Self {
+        Self {
+            size,
+            from,
+            to,
+            kind: TransitionKind::Fade,
+            progress: 0.0,
+        }
+    }
+    pub fn kind(mut self, kind: TransitionKind) -> Self {
+        self.kind = kind;
+        self
+    }
+    pub fn progress(mut self, progress: f32) -> Self {
+        self.progress = progress.clamp(0.0, 1.0);
+        self
+    }
+}
+
+/// The part of a `src`-sized texture left visible when it covers `dst`,
+/// as `(u0, v0, u1, v1)`.
+fn cover_window(src: Size<u32>, dst_w: f32, dst_h: f32) -> [f32; 4] {
+    let src_aspect = src.width as f32 / src.height as f32;
+    let dst_aspect = dst_w / dst_h;
+    if src_aspect > dst_aspect {
+        let f = dst_aspect / src_aspect;
+        [(1.0 - f) * 0.5, 0.0, (1.0 + f) * 0.5, 1.0]
+    } else {
+        let f = src_aspect / dst_aspect;
+        [0.0, (1.0 - f) * 0.5, 1.0, (1.0 + f) * 0.5]
+    }
+}
+
+/// Same layout as WGSL's `unpack2x16unorm`.
+fn pack_unorm2x16(x: f32, y: f32) -> u32 {
+    let q = |v: f32| (v.clamp(0.0, 1.0) * 65535.0).round() as u32;
+    q(x) | (q(y) << 16)
+}
+
+/// Draws the horizontal slice `[a, b]` (fractions of the rect width) of
+/// `handle`, mapped onto the slice `[ua, ub]` of its cover window.
+struct Layer {
+    handle: TextureHandle,
+    a: f32,
+    b: f32,
+    ua: f32,
+    ub: f32,
+    alpha: u8,
+}
+
+impl Layer {
+    fn full(handle: TextureHandle) -> Self {
+        Self {
+            handle,
+            a: 0.0,
+            b: 1.0,
+            ua: 0.0,
+            ub: 1.0,
+            alpha: 255,
+        }
+    }
+
+    fn push(&self, (x, y): (f32, f32), (w, h): (f32, f32), out: &mut Vec<Instance>) {
+        let src = self.handle.size_px;
+        if src.width == 0 || src.height == 0 || self.b <= self.a || self.alpha == 0 {
+            return;
+        }
+        let [u0, v0, u1, v1] = cover_window(src, w, h);
+        let du = u1 - u0;
+
+        out.push(Instance::new(
+            PipelineKey::Other("wallpaper_transition"),
+            Position::new(x + w * self.a, y),
+            Size::new((w * (self.b - self.a)).max(1.0), h),
+            // data1 maps to `@location(2) style` in transition_shader.wgsl
+            // index 0: Tint Color (alpha is the layer opacity)
+            // index 1/2: uv window, min and max corner
+            [
+                Color::rgba(255, 255, 255, self.alpha).0,
+                pack_unorm2x16(u0 + du * self.ua, v0),
+                pack_unorm2x16(u0 + du * self.ub, v1),
+                0,
+            ],
+            [
+                self.handle.slot_gen,
+                self.handle.scale_packed,
+                self.handle.offset_packed,
+                0,
+            ],
+        ));
+    }
+}
+
+impl IntoElement for TransitionImage {}
+
+impl Widget for TransitionImage {
+    fn layout<'a>(&mut self, _ctx: &mut LayoutCtx<'a>) -> Node {
+        Node {
+            size: self.size,
+            min: Size::splat(0),
+            max: Size::splat(i32::MAX),
+            ..Default::default()
+        }
+    }
+
+    fn child_count(&self) -> usize {
+        0
+    }
+    fn child_mut(&mut self, _i: usize) -> &mut dyn Widget {
+        unreachable!()
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, out: &mut Vec<Instance>) {
+        let r = ctx.rect();
+        if r.w <= 0 || r.h <= 0 {
+            return;
+        }
+
+        // Smoothstep, so the motion eases in and out.
+        let p = self.progress * self.progress * (3.0 - 2.0 * self.progress);
+        let (from, to) = match self.kind {
+            TransitionKind::None => (Layer::full(self.to), None),
+            TransitionKind::Fade => (
+                Layer::full(self.from),
+                Some(Layer {
+                    alpha: (p * 255.0).round() as u8,
+                    ..Layer::full(self.to)
+                }),
+            ),
+            TransitionKind::Slide => (
+                Layer {
+                    b: 1.0 - p,
+                    ua: p,
+                    ..Layer::full(self.from)
+                },
+                Some(Layer {
+                    a: 1.0 - p,
+                    ub: p,
+                    ..Layer::full(self.to)
+                }),
+            ),
+            TransitionKind::Wipe => (
+                Layer {
+                    a: p,
+                    ua: p,
+                    ..Layer::full(self.from)
+                },
+                Some(Layer {
+                    b: p,
+                    ub: p,
+                    ..Layer::full(self.to)
+                }),
+            ),
+        };
+
+        let origin = (r.x as f32, r.y as f32);
+        let size = (r.w as f32, r.h as f32);
+        from.push(origin, size, out);
+        if let Some(to) = to {
+            to.push(origin, size, out);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::{any::Any, fmt, marker::PhantomData, sync::Arc};
+
+/// A typed message topic. Like [`Key`](crate::Key), the payload type is fixed
+/// at the definition site, so publishers and subscribers sharing the constant
+/// cannot disagree on it. Declare shared topics in a common crate:
+///
+/// ```ignore
+/// pub const LOCKED: Topic<bool> = Topic::new("lockscreen/locked");
+/// ```
+pub struct Topic<T> {
+    pub id: &'static str,
+    _ty: PhantomData<fn() -> T>,
+}
+
+impl<T> Topic<T> {
+    pub const fn new(id: &'static str) -> Self {
+        Self {
+            id,
+            _ty: PhantomData,
+        }
+    }
+}
+impl<T> Clone for Topic<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for Topic<T> {}
+
+type Payload = dyn Any + Send + Sync;
+type MapFn<M> = Arc<dyn Fn(&Payload) -> Option<M> + Send + Sync>;
+
+/// A message on its way to every module subscribed to its topic. Built by
+/// [`Task::publish`](crate::Task::publish), routed by orbitd.
+#[derive(Clone)]
+pub struct Published {
+    pub topic: &'static str,
+    payload: Arc<Payload>,
+}
+
+impl Published {
+    pub fn new<T: Send + Sync + 'static>(topic: Topic<T>, value: T) -> Self {
+        Self {
+            topic: topic.id,
+            payload: Arc::new(value),
+        }
+    }
+}
+
+impl fmt::Debug for Published {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Published({})", self.topic)
+    }
+}
+
+/// Turns messages published on one topic into the subscriber's own message
+/// type. Built by [`Subscription::topic`](crate::Subscription::topic).
+pub struct TopicSubscription<M> {
+    pub topic: &'static str,
+    map: MapFn<M>,
+}
+
+impl<M> Clone for TopicSubscription<M> {
+    fn clone(&self) -> Self {
+        Self {
+            topic: self.topic,
+            map: Arc::clone(&self.map),
+        }
+    }
+}
+
+impl<M: 'static> TopicSubscription<M> {
+    pub fn new<T: Clone + 'static>(
+        topic: Topic<T>,
+        f: impl Fn(T) -> M + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            topic: topic.id,
+            map: Arc::new(move |payload: &Payload| payload.downcast_ref::<T>().cloned().map(&f)),
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn map<N>(self, f: impl Fn(M) -> N + Send + Sync + 'static) -> TopicSubscription<N> {
+        let map = self.map;
+        TopicSubscription {
+            topic: self.topic,
+            map: Arc::new(move |payload: &Payload| map(payload).map(&f)),
+        }
+    }
+
+    /// `None` if `msg` is on another topic or its payload has another type.
+    #[doc(hidden)]
+    pub fn deliver(&self, msg: &Published) -> Option<M> {
+        if msg.topic != self.topic {
+            return None;
+        }
+        (self.map)(&*msg.payload)
+    }
+}
@@ -1,4 +1,13 @@
-use std::{fmt, pin::Pin, sync::Arc, time::Duration};
+use std::{
+    fmt,
+    pin::Pin,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
 
 use serde::{Serialize, de::DeserializeOwned};
 use ui::{sctk::SctkEvent, widget::Element};
@@ -9,6 +18,8 @@ pub use tracing;
 pub use ui;
 #[doc(hidden)]
 pub mod runtime;
+pub use blocking::unblock;
+pub use bus::*;
 pub use resource::*;
 #[doc(hidden)]
 pub use schemars;
@@ -19,6 +30,7 @@ pub use serde_json;
 #[doc(hidden)]
 pub use yaml_serde;
 
+mod bus;
 mod resource;
 
 pub type Event<M> = ui::event::Event<M, SctkEvent>;
@@ -46,18 +58,78 @@ pub enum Task<M: Send + 'static> {
     ExitOrbit,
 
     Spawn(BoxFuture<M>),
+    Publish(Published),
 }
 
 impl<M: Send + 'static> Task<M> {
     pub fn batch(tasks: impl IntoIterator<Item = Task<M>>) -> Self {
         Self::Batch(tasks.into_iter().collect())
     }
+    /// Run `fut` on the worker pool shared by every module, delivering its
+    /// output as a message. The pool has only a few threads and a future is
+    /// only cancelled at an `.await`, so `fut` must not block: anything CPU-
+    /// or IO-bound goes through [`Task::blocking`] or [`unblock`].
     pub fn spawn<F>(fut: F) -> Self
     where
         F: Future<Output = M> + Send + 'static,
     {
         Self::Spawn(Box::pin(fut))
     }
+    /// Run `f` on a separate pool meant for blocking work, like decoding or
+    /// reading files, delivering its result as a message. Hiding the module
+    /// drops the result, but `f` itself runs to completion once started.
+    pub fn blocking<F>(f: F) -> Self
+    where
+        F: FnOnce() -> M + Send + 'static,
+    {
+        Self::spawn(blocking::unblock(f))
+    }
+    /// Send `value` to every other module subscribed to `topic`.
+    pub fn publish<T: Send + Sync + 'static>(topic: Topic<T>, value: T) -> Self {
+        Self::Publish(Published::new(topic, value))
+    }
+}
+
+type ReplyFn = Box<dyn FnOnce(Option<String>) + Send + 'static>;
+
+/// Arguments of a command sent with `orbit command <module> <command>
+/// [args...]`, or piped in line by line, and the way to answer the caller.
+#[derive(Clone)]
+pub struct CommandArgs {
+    pub args: Vec<String>,
+    reply: Arc<Mutex<Option<ReplyFn>>>,
+}
+
+impl CommandArgs {
+    #[doc(hidden)]
+    pub fn new(args: Vec<String>, reply: impl FnOnce(Option<String>) + Send + 'static) -> Self {
+        Self {
+            args,
+            reply: Arc::new(Mutex::new(Some(Box::new(reply)))),
+        }
+    }
+
+    /// Answers the caller, who prints `answer`, or fails on `None`. Only the
+    /// first answer counts, and dropping every clone without one answers
+    /// `None`.
+    pub fn reply(&self, answer: Option<String>) {
+        if let Some(reply) = self.reply.lock().unwrap().take() {
+            reply(answer);
+        }
+    }
+
+    /// `true` once the caller was answered.
+    pub fn is_answered(&self) -> bool {
+        self.reply.lock().unwrap().is_none()
+    }
+}
+
+impl fmt::Debug for CommandArgs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CommandArgs")
+            .field("args", &self.args)
+            .finish_non_exhaustive()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -76,19 +148,111 @@ impl fmt::Display for SendError {
 
 impl std::error::Error for SendError {}
 
+#[derive(Default)]
+struct CloseState {
+    closed: AtomicBool,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+/// Runtime side of a stream's cancellation signal, see
+/// [`SubscriptionSender::closed`].
+#[doc(hidden)]
+#[derive(Clone)]
+pub struct CloseHandle(Arc<CloseState>);
+
+impl CloseHandle {
+    pub fn close(&self) {
+        if !self.0.closed.swap(true, Ordering::AcqRel) {
+            let wakers = std::mem::take(&mut *self.0.wakers.lock().unwrap());
+            for waker in wakers {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Future returned by [`SubscriptionSender::closed`].
+pub struct Closed(Arc<CloseState>);
+
+impl Future for Closed {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0.closed.load(Ordering::Acquire) {
+            return Poll::Ready(());
+        }
+        let mut wakers = self.0.wakers.lock().unwrap();
+        // Re-check under the lock so a concurrent `close` can't be missed.
+        if self.0.closed.load(Ordering::Acquire) {
+            return Poll::Ready(());
+        }
+        if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
 pub struct SubscriptionSender<M> {
     pub(crate) inner: Arc<dyn Fn(M) -> Result<(), SendError> + Send + Sync + 'static>,
+    close: Arc<CloseState>,
+}
+
+impl<M> Clone for SubscriptionSender<M> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+            close: Arc::clone(&self.close),
+        }
+    }
 }
 
 impl<M: Send + 'static> SubscriptionSender<M> {
     #[doc(hidden)]
     pub fn new(f: Arc<dyn Fn(M) -> Result<(), SendError> + Send + Sync + 'static>) -> Self {
-        Self { inner: f }
+        Self {
+            inner: f,
+            close: Arc::default(),
+        }
+    }
+
+    /// Sender feeding `self` through `f`, sharing its cancellation signal.
+    #[doc(hidden)]
+    pub fn map<N: Send + 'static>(
+        self,
+        f: impl Fn(N) -> M + Send + Sync + 'static,
+    ) -> SubscriptionSender<N> {
+        let close = Arc::clone(&self.close);
+        SubscriptionSender {
+            inner: Arc::new(move |msg| self.send(f(msg))),
+            close,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn close_handle(&self) -> CloseHandle {
+        CloseHandle(Arc::clone(&self.close))
     }
 
     pub fn send(&self, msg: M) -> Result<(), SendError> {
+        if self.is_closed() {
+            return Err(SendError::Disconnected);
+        }
         (self.inner)(msg)
     }
+
+    /// `true` once the subscription was torn down (the module got hidden or
+    /// unloaded). Work running outside the stream's future, like a helper
+    /// thread holding a clone, should check this and stop.
+    pub fn is_closed(&self) -> bool {
+        self.close.closed.load(Ordering::Acquire)
+    }
+
+    /// Resolves once the subscription is torn down, for racing against
+    /// long-running waits that need cleanup before the stream ends.
+    pub fn closed(&self) -> Closed {
+        Closed(Arc::clone(&self.close))
+    }
 }
 
 pub type BoxStreamFactory<M> =
@@ -102,9 +266,14 @@ pub enum Subscription<M: Send + 'static> {
     SyncedInterval { every: Duration, message: M },
     SyncedTimeout { after: Duration, message: M },
     Stream(BoxStreamFactory<M>),
+    Topic(TopicSubscription<M>),
+    Background(Box<Subscription<M>>),
 }
 
 impl<M: Send + 'static> Subscription<M> {
+    /// Run the future `f` makes on the shared worker pool, for as long as the
+    /// subscription lives. Like [`Task::spawn`], it must not block; await
+    /// [`unblock`] for blocking steps.
     pub fn stream<F, Fut>(f: F) -> Self
     where
         F: FnOnce(SubscriptionSender<M>) -> Fut + Send + 'static,
@@ -112,6 +281,20 @@ impl<M: Send + 'static> Subscription<M> {
     {
         Self::Stream(Box::new(|tx| Box::pin(f(tx))))
     }
+
+    /// Receive what other modules publish on `topic`, mapped through `f`.
+    pub fn topic<T: Clone + 'static>(
+        topic: Topic<T>,
+        f: impl Fn(T) -> M + Send + Sync + 'static,
+    ) -> Self {
+        Self::Topic(TopicSubscription::new(topic, f))
+    }
+
+    /// Run `sub` for as long as the module is loaded, even while hidden.
+    /// Messages it produces are delivered to `update` with no target.
+    pub fn background(sub: Subscription<M>) -> Self {
+        Self::Background(Box::new(sub))
+    }
 }
 
 impl<M: Send + Clone + 'static> Clone for Subscription<M> {
@@ -135,6 +318,8 @@ impl<M: Send + Clone + 'static> Clone for Subscription<M> {
                 after: *after,
                 message: message.clone(),
             },
+            Self::Topic(t) => Self::Topic(t.clone()),
+            Self::Background(s) => Self::Background(Box::new((**s).clone())),
             // FnOnce factories are non-clonable; degrade gracefully.
             Self::Stream(_) => Self::None,
         }
@@ -177,13 +362,15 @@ pub trait OrbitModule: Default + 'static {
         let _ = (ctl, tid, engine, event);
         Task::None
     }
+    /// Called on each of this module's targets showing the changed scope, but
+    /// only for keys this module has leased before.
     fn on_broadcast(
         &mut self,
         ctl: &mut OrbitCtl<'_>,
         tid: Option<ui::graphics::TargetId>,
-        key: &'static str,
+        broadcast: &Broadcast,
     ) -> Task<Self::Message> {
-        let _ = (ctl, tid, key);
+        let _ = (ctl, tid, broadcast);
         Task::None
     }
     fn view(&self, tid: &ui::graphics::TargetId, theme: &ui::theme::Theme) -> Element;
@@ -42,6 +42,22 @@ impl OutputInfo {
     }
 }
 
+/// What happened to a key, as seen by `on_broadcast`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BroadcastKind {
+    Published,
+    Revoked,
+}
+
+/// A change to a published key. `output` is the output whose slot changed, or
+/// `None` for a global key.
+#[derive(Clone, Debug)]
+pub struct Broadcast {
+    pub key: &'static str,
+    pub kind: BroadcastKind,
+    pub output: Option<OutputInfo>,
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum Scope {
     Global,
@@ -100,33 +116,38 @@ impl Reclaim for Atlas {
 }
 
 type Sink = Rc<RefCell<Vec<Box<dyn Reclaim>>>>;
+type Reclaimer<T> = (Sink, fn(T) -> Box<dyn Reclaim>);
 
-struct ResCell<T: Reclaim> {
+struct ResCell<T: 'static> {
     value: Option<T>,
-    sink: Sink,
+    /// `None` for plain data, which is simply dropped with its last lease.
+    reclaim: Option<Reclaimer<T>>,
 }
-impl<T: Reclaim> Drop for ResCell<T> {
+impl<T: 'static> Drop for ResCell<T> {
     fn drop(&mut self) {
-        if let Some(v) = self.value.take() {
-            self.sink.borrow_mut().push(Box::new(v));
+        if let Some(v) = self.value.take()
+            && let Some((sink, boxed)) = &self.reclaim
+        {
+            sink.borrow_mut().push(boxed(v));
         }
     }
 }
 
 /// Pinned handle to a published resource. While ANY lease is alive the resource
 /// is not reclaimed. Read through `Deref`; never copy the inner value out and
-/// cache it - the pin is the `Rc`, not the (possibly `Copy`) value.
-pub struct Lease<T: Reclaim> {
+/// cache it - the pin is the `Rc`, not the (possibly `Copy`) value. Plain data
+/// published with `publish_data` has nothing to reclaim and may be cloned out.
+pub struct Lease<T: 'static> {
     cell: Rc<ResCell<T>>,
 }
-impl<T: Reclaim> Clone for Lease<T> {
+impl<T: 'static> Clone for Lease<T> {
     fn clone(&self) -> Self {
         Self {
             cell: Rc::clone(&self.cell),
         }
     }
 }
-impl<T: Reclaim> Deref for Lease<T> {
+impl<T: 'static> Deref for Lease<T> {
     type Target = T;
     fn deref(&self) -> &T {
         self.cell.value.as_ref().expect("present until last drop")
@@ -137,8 +158,9 @@ type Slot = (&'static str, Option<OutputTag>);
 
 /// Output-scoped, type-erased resource store with refcounted leases. Knows
 /// nothing about modules; the only GPU knowledge is the `Reclaim` impl, invoked
-/// by the Engine owner. Scoped access is private - only `OrbitCtl` (same module)
-/// resolves a key's scope into a slot, so modules can never key it by hand.
+/// by the Engine owner. Plain data lives in the same slots without one. Scoped
+/// access is private - only `OrbitCtl` (same module) resolves a key's scope
+/// into a slot, so modules can never key it by hand.
 #[derive(Default)]
 pub struct ResourceManager {
     leased: HashMap<Slot, Box<dyn Any>>, // each box is an `Rc<ResCell<T>>`
@@ -146,13 +168,28 @@ pub struct ResourceManager {
 }
 impl ResourceManager {
     fn publish_at<T: Reclaim>(&mut self, id: &'static str, tag: Option<OutputTag>, val: T) {
+        fn boxed<T: Reclaim>(v: T) -> Box<dyn Reclaim> {
+            Box::new(v)
+        }
         let cell = Rc::new(ResCell {
             value: Some(val),
-            sink: Rc::clone(&self.sink),
+            reclaim: Some((Rc::clone(&self.sink), boxed::<T>)),
         });
         self.leased.insert((id, tag), Box::new(cell));
     }
-    fn lease_at<T: Reclaim>(&self, id: &'static str, tag: Option<OutputTag>) -> Option<Lease<T>> {
+    fn publish_data_at<T: Clone + 'static>(
+        &mut self,
+        id: &'static str,
+        tag: Option<OutputTag>,
+        val: T,
+    ) {
+        let cell = Rc::new(ResCell {
+            value: Some(val),
+            reclaim: None,
+        });
+        self.leased.insert((id, tag), Box::new(cell));
+    }
+    fn lease_at<T: 'static>(&self, id: &'static str, tag: Option<OutputTag>) -> Option<Lease<T>> {
         let cell = self
             .leased
             .get(&(id, tag))?
@@ -180,12 +217,15 @@ impl ResourceManager {
 
 /// Per-call context: the shared store plus this call's surface/output identity.
 /// `publish`/`revoke` record a dirty entry; the daemon harvests it after the
-/// call and emits the change broadcast. Modules never emit one by hand.
+/// call and emits the change broadcast. Modules never emit one by hand. Keys
+/// the module leases are recorded too, so broadcasts only reach modules that
+/// read the key.
 pub struct OrbitCtl<'a> {
     resources: &'a mut ResourceManager,
     tid: Option<TargetId>,
     output: Option<OutputInfo>,
-    dirty: Vec<Slot>,
+    dirty: Vec<(&'static str, Option<OutputTag>, BroadcastKind)>,
+    leased: RefCell<Vec<&'static str>>,
 }
 impl<'a> OrbitCtl<'a> {
     pub fn new(
@@ -198,6 +238,7 @@ impl<'a> OrbitCtl<'a> {
             tid,
             output,
             dirty: Vec::new(),
+            leased: RefCell::default(),
         }
     }
 
@@ -225,17 +266,32 @@ impl<'a> OrbitCtl<'a> {
             return false;
         };
         self.resources.publish_at(id, tag, val);
-        self.dirty.push((id, tag));
+        self.dirty.push((id, tag, BroadcastKind::Published));
         true
     }
-    pub fn lease<T: Reclaim>(&self, key: Key<T>) -> Option<Lease<T>> {
+    /// Publish plain data (no GPU state, nothing to reclaim) for this call's
+    /// output or globally. Same leases and broadcasts as [`publish`](Self::publish).
+    pub fn publish_data<T: Clone + 'static>(&mut self, key: Key<T>, val: T) -> bool {
+        let Some((id, tag)) = self.slot(key) else {
+            return false;
+        };
+        self.resources.publish_data_at(id, tag, val);
+        self.dirty.push((id, tag, BroadcastKind::Published));
+        true
+    }
+    pub fn lease<T: 'static>(&self, key: Key<T>) -> Option<Lease<T>> {
+        self.leased.borrow_mut().push(key.id);
         let (id, tag) = self.slot(key)?;
         self.resources.lease_at(id, tag)
     }
+    /// Copy of a plain-data value, for when holding a lease isn't needed.
+    pub fn get<T: Clone + 'static>(&self, key: Key<T>) -> Option<T> {
+        self.lease(key).map(|l| (*l).clone())
+    }
     pub fn revoke<T>(&mut self, key: Key<T>) {
         if let Some((id, tag)) = self.slot(key) {
             self.resources.revoke_at(id, tag);
-            self.dirty.push((id, tag));
+            self.dirty.push((id, tag, BroadcastKind::Revoked));
         }
     }
 
@@ -248,19 +304,36 @@ impl<'a> OrbitCtl<'a> {
             Scope::PerOutput => Some(out.tag),
         };
         self.resources.publish_at(key.id, tag, val);
-        self.dirty.push((key.id, tag));
+        self.dirty.push((key.id, tag, BroadcastKind::Published));
+    }
+    pub fn publish_data_on<T: Clone + 'static>(&mut self, out: &OutputInfo, key: Key<T>, val: T) {
+        let tag = match key.scope {
+            Scope::Global => None,
+            Scope::PerOutput => Some(out.tag),
+        };
+        self.resources.publish_data_at(key.id, tag, val);
+        self.dirty.push((key.id, tag, BroadcastKind::Published));
     }
-    pub fn lease_on<T: Reclaim>(&self, out: &OutputInfo, key: Key<T>) -> Option<Lease<T>> {
+    pub fn lease_on<T: 'static>(&self, out: &OutputInfo, key: Key<T>) -> Option<Lease<T>> {
+        self.leased.borrow_mut().push(key.id);
         let tag = match key.scope {
             Scope::Global => None,
             Scope::PerOutput => Some(out.tag),
         };
         self.resources.lease_at(key.id, tag)
     }
+    pub fn get_on<T: Clone + 'static>(&self, out: &OutputInfo, key: Key<T>) -> Option<T> {
+        self.lease_on(out, key).map(|l| (*l).clone())
+    }
 
     #[doc(hidden)]
     /// Daemon-only: drain recorded changes to emit as broadcasts.
-    pub fn take_dirty(&mut self) -> Vec<(&'static str, Option<OutputTag>)> {
+    pub fn take_dirty(&mut self) -> Vec<(&'static str, Option<OutputTag>, BroadcastKind)> {
         std::mem::take(&mut self.dirty)
     }
+    #[doc(hidden)]
+    /// Daemon-only: drain the keys leased during this call.
+    pub fn take_leased(&mut self) -> Vec<&'static str> {
+        std::mem::take(self.leased.get_mut())
+    }
 }
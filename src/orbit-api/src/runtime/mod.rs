@@ -5,7 +5,7 @@ use ui::{
     widget::Element,
 };
 
-use crate::{ErasedMsg, Event, OrbitCtl, Subscription, Task};
+use crate::{Broadcast, CommandArgs, ErasedMsg, Event, OrbitCtl, Subscription, Task};
 
 pub mod erased;
 
@@ -42,10 +42,11 @@ pub trait OrbitModuleDyn: 'static {
         &mut self,
         ctl: &mut OrbitCtl<'_>,
         tid: Option<TargetId>,
-        key: &'static str,
+        broadcast: &Broadcast,
     ) -> Task<ErasedMsg>;
     fn view(&self, tid: &TargetId, theme: &Theme) -> Element;
     fn command_message(&self, command: &str) -> Option<ErasedMsg>;
+    fn command_args_message(&self, command: &str, args: CommandArgs) -> Option<ErasedMsg>;
 
     fn subscriptions(&self) -> Subscription<ErasedMsg>;
 }
@@ -7,17 +7,23 @@ use std::{
 
 use yaml_serde::{Mapping, Value};
 
+use crate::trust::TrustedModules;
+
 #[derive(Default, Debug)]
 pub struct Config {
     /// `modules: { bar: true, launcher: false }`. The enable/disable map.
+    /// Keys may name an instance (`bar@top`), see [`split_instance`].
     pub modules: HashMap<String, bool>,
-    /// Per-module config blobs, every top-level key that isn't `modules` or
-    /// `modules_dir` ends up here.
+    /// Per-module config blobs, every top-level key that isn't `modules`,
+    /// `modules_dir` or `trusted_modules` ends up here.
     pub config: HashMap<String, Value>,
     /// Optional override for the user modules directory.
     /// When set, this path is used *instead of* the default
     /// `<config_home>/modules`.  It does **not** affect the system modules dir.
     pub modules_dir_override: Option<PathBuf>,
+    /// Optional allowlist user modules must pass before they are loaded.
+    /// Absent means ownership and mode bits are the only requirement.
+    pub trusted_modules: Option<Box<TrustedModules>>,
     /// The raw mapping, kept for forward-compat / future use.
     pub extra: Mapping,
 }
@@ -38,6 +44,13 @@ impl Config {
                 let Some(name) = k.as_str() else {
                     return Err("modules key is not a string".into());
                 };
+                if let (base, Some(instance)) = split_instance(name)
+                    && (base.is_empty() || instance.is_empty() || instance.contains('@'))
+                {
+                    return Err(format!(
+                        "modules.{name} is not a valid instance, expected `<module>@<instance>`"
+                    ));
+                }
                 let Some(b) = v.as_bool() else {
                     return Err(format!("modules.{name} is not a bool"));
                 };
@@ -57,10 +70,15 @@ impl Config {
             }
         }
 
+        // Parse optional `trusted_modules:` allowlist.
+        if let Some(trust_val) = root.get("trusted_modules") {
+            out.trusted_modules = Some(Box::new(TrustedModules::from_value(trust_val)?));
+        }
+
         // Everything else is per-module config.
         for (k, v) in root {
             let Some(key) = k.as_str() else { continue };
-            if key == "modules" || key == "modules_dir" {
+            if matches!(key, "modules" | "modules_dir" | "trusted_modules") {
                 continue;
             }
             out.config.insert(key.to_owned(), v.clone());
@@ -75,9 +93,29 @@ impl Config {
         self.modules.get(name).copied().unwrap_or(false)
     }
 
+    /// Config block for `name`. An instance without its own block falls back
+    /// to the block of the module it instantiates.
     #[inline]
     pub fn get(&self, name: &str) -> Option<&Value> {
-        self.config.get(name)
+        self.config
+            .get(name)
+            .or_else(|| self.config.get(split_instance(name).0))
+    }
+
+    /// Instances (`bar@top`, `bar@bottom`, ...) of `base` declared under `modules:`.
+    pub fn instances_of<'a>(&'a self, base: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.modules
+            .keys()
+            .filter(move |name| matches!(split_instance(name), (b, Some(_)) if b == base))
+            .map(String::as_str)
+    }
+}
+
+/// Split `bar@top` into `("bar", Some("top"))`; plain names yield `None`.
+pub fn split_instance(name: &str) -> (&str, Option<&str>) {
+    match name.split_once('@') {
+        Some((base, instance)) => (base, Some(instance)),
+        None => (name, None),
     }
 }
 
@@ -86,6 +124,7 @@ impl PartialEq for Config {
         self.modules == other.modules
             && self.config == other.config
             && self.modules_dir_override == other.modules_dir_override
+            && self.trusted_modules == other.trusted_modules
     }
 }
 
@@ -1,34 +1,46 @@
 use std::{
-    fs,
-    os::unix::fs::MetadataExt,
-    path::{Path, PathBuf},
+    collections::HashMap,
+    fs::{self, File},
+    os::{
+        fd::{AsFd, AsRawFd},
+        unix::fs::MetadataExt,
+    },
+    path::{Component, Path, PathBuf},
 };
 
 use crate::{
-    config::Config,
+    config::{Config, split_instance},
+    loader::{ModuleFile, openat},
     xdg::{SYSTEM_MODULES_DIR, default_user_modules_dir},
 };
 
 #[derive(Debug, Clone)]
 pub struct DiscoveredModule {
+    /// `bar`, or `bar@top` for an instance.
     pub name: String,
-    pub path: PathBuf,
+    pub file: ModuleFile,
     pub enabled: bool,
+    /// Why the module must not be loaded (failed permission or trust checks).
+    /// Refused modules are still listed so the reason can be shown.
+    pub refused: Option<String>,
 }
 
-// FIX: TOCTOU and parent dir check, these are security risks
-//
-/// Returns `Ok(())` when `path` passes our security requirements:
+/// Returns `Ok(())` when `file` passes our security requirements:
 ///
-/// * The file must be owned by the current effective user.
+/// * The file must be a regular file owned by the current effective user.
 /// * The file must not be group- or world-writable (prevents another user or a
 ///   compromised process from swapping in a malicious `.so`).
 ///
+/// Checked with `fstat` on the descriptor that will later be loaded.
 /// System-directory files (`/usr/lib/orbit/modules/*`) are trusted
 /// unconditionally — they are installed by the package manager, root-owned,
 /// and not writeable by ordinary users.
-fn check_user_file_permissions(path: &Path) -> Result<(), String> {
-    let meta = fs::metadata(path).map_err(|e| format!("could not stat {}: {e}", path.display()))?;
+fn check_user_file_permissions(file: &ModuleFile) -> Result<(), String> {
+    let meta = file.metadata()?;
+    let path = file.path();
+    if !meta.is_file() {
+        return Err(format!("{} is not a regular file", path.display()));
+    }
 
     let euid = unsafe { libc::geteuid() };
     if meta.uid() != euid {
@@ -51,17 +63,87 @@ fn check_user_file_permissions(path: &Path) -> Result<(), String> {
     Ok(())
 }
 
-fn scan_dir(
-    dir: &Path,
-    is_user: bool,
-    map: &mut std::collections::HashMap<String, (PathBuf, bool)>,
-) {
-    let Ok(entries) = fs::read_dir(dir) else {
+/// A directory on the way to the user modules must be owned by root or the
+/// current user and not be writable by anyone else. Sticky ancestors such as
+/// `/tmp` are tolerated, since others cannot replace entries in them; the
+/// modules directory itself never is.
+fn check_dir_permissions(dir: &File, path: &Path, allow_sticky: bool) -> Result<(), String> {
+    let meta = dir
+        .metadata()
+        .map_err(|e| format!("could not stat {}: {e}", path.display()))?;
+
+    let euid = unsafe { libc::geteuid() };
+    if meta.uid() != euid && meta.uid() != 0 {
+        return Err(format!(
+            "{} is not owned by the current user or root (uid {euid}, owner uid {})",
+            path.display(),
+            meta.uid()
+        ));
+    }
+
+    // S_IWGRP = 0o020, S_IWOTH = 0o002, S_ISVTX = 0o1000
+    let mode = meta.mode();
+    if mode & 0o022 != 0 && !(allow_sticky && mode & 0o1000 != 0) {
+        return Err(format!(
+            "{} is group- or world-writable (mode {mode:#o}); refusing to load from it",
+            path.display()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Open `dir` one component at a time from `/` without following symlinks,
+/// so the returned descriptor is the very directory that was checked. With
+/// `verify`, every directory on the way is checked as well.
+fn open_dir(dir: &Path, verify: bool) -> Result<File, String> {
+    let canonical =
+        fs::canonicalize(dir).map_err(|e| format!("could not resolve {}: {e}", dir.display()))?;
+    let flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_NOFOLLOW;
+
+    let mut current = PathBuf::from("/");
+    let mut fd = File::open(&current).map_err(|e| format!("could not open /: {e}"))?;
+
+    let mut components = canonical.components().peekable();
+    loop {
+        if verify {
+            let is_modules_dir = components.peek().is_none();
+            check_dir_permissions(&fd, &current, !is_modules_dir)?;
+        }
+
+        let Some(component) = components.next() else {
+            return Ok(fd);
+        };
+        let Component::Normal(name) = component else {
+            continue;
+        };
+        current.push(name);
+        fd = openat(fd.as_fd(), Path::new(name), flags)
+            .map(File::from)
+            .map_err(|e| format!("could not open {}: {e}", current.display()))?;
+    }
+}
+
+fn scan_dir(dir: &Path, is_user: bool, map: &mut HashMap<String, (ModuleFile, bool)>) {
+    let dir_fd = match open_dir(dir, is_user) {
+        Ok(fd) => fd,
+        Err(e) => {
+            if is_user {
+                tracing::warn!(error = %e, "skipping user modules directory");
+            }
+            return;
+        }
+    };
+
+    // List through the descriptor so names come from the directory we checked.
+    let listing = PathBuf::from(format!("/proc/self/fd/{}", dir_fd.as_raw_fd()));
+    let Ok(entries) = fs::read_dir(&listing) else {
         return;
     };
 
     for entry in entries.flatten() {
-        let path = entry.path();
+        let file_name = entry.file_name();
+        let path = dir.join(&file_name);
         if path.extension().map(|e| e == "so").unwrap_or(false)
             && let Some(stem) = path
                 .file_stem()
@@ -70,7 +152,13 @@ fn scan_dir(
                 // (libbar.so → bar).
                 .map(|s| s.strip_prefix("lib").unwrap_or(s))
         {
-            map.insert(stem.to_owned(), (path, is_user));
+            let stem = stem.to_owned();
+            match ModuleFile::open_at(dir_fd.as_fd(), Path::new(&file_name), path) {
+                Ok(file) => {
+                    map.insert(stem, (file, is_user));
+                }
+                Err(e) => tracing::warn!(module = %stem, error = %e, "skipping module"),
+            }
         }
     }
 }
@@ -79,8 +167,16 @@ fn scan_dir(
 /// uniquely-named `.so` file.  User modules shadow system modules of the same
 /// name.
 ///
-/// Only user-directory entries are permission-checked.  System-directory
-/// entries are trusted by virtue of being installed by the package manager.
+/// Every instance declared under `modules:` (`bar@top`) gets an entry of its
+/// own, backed by the same `.so` as its base module.
+///
+/// Only user-directory entries (and the directories leading to them) are
+/// permission-checked, and verified against `trusted_modules` when that is
+/// configured.  System-directory entries are trusted by virtue of being
+/// installed by the package manager.  Either way each module is returned as an
+/// open [`ModuleFile`], which is what gets loaded later (for a verified module,
+/// a sealed copy of the bytes that were verified); modules failing a
+/// check come back with `refused` set and must not be loaded.
 ///
 /// The `modules_dir_override` field of `config` (if set) replaces the default
 /// user path (`<config_home>/modules`) but never affects the system path.
@@ -90,8 +186,7 @@ pub fn discover_modules(config_home: &Path, config: &Config) -> Vec<DiscoveredMo
         .clone()
         .unwrap_or_else(|| default_user_modules_dir(config_home));
 
-    let mut by_name: std::collections::HashMap<String, (PathBuf, bool /* is_user */)> =
-        std::collections::HashMap::new();
+    let mut by_name: HashMap<String, (ModuleFile, bool /* is_user */)> = HashMap::new();
 
     scan_dir(Path::new(SYSTEM_MODULES_DIR), false, &mut by_name);
 
@@ -105,27 +200,60 @@ pub fn discover_modules(config_home: &Path, config: &Config) -> Vec<DiscoveredMo
         );
     }
 
-    let mut items: Vec<(String, PathBuf, bool)> = by_name
+    let mut items: Vec<(String, ModuleFile, bool)> = by_name
         .into_iter()
-        .map(|(name, (path, is_user))| (name, path, is_user))
+        .map(|(name, (file, is_user))| (name, file, is_user))
         .collect();
     items.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
 
     let mut result = Vec::with_capacity(items.len());
 
-    for (name, path, is_user) in items {
-        if is_user && let Err(e) = check_user_file_permissions(&path) {
-            tracing::warn!(module = %name, error = %e, "skipping module");
-            continue;
+    for (name, file, is_user) in items {
+        // A verified module is loaded from the sealed copy that was hashed.
+        let (file, refused) = if is_user {
+            let checked =
+                check_user_file_permissions(&file).and_then(|()| match &config.trusted_modules {
+                    Some(trusted) => trusted.verify(&file),
+                    None => Ok(file.clone()),
+                });
+            match checked {
+                Ok(checked) => (checked, None),
+                Err(reason) => (file, Some(reason)),
+            }
+        } else {
+            (file, None)
+        };
+        if let Some(reason) = &refused {
+            tracing::warn!(module = %name, reason = %reason, "refusing module");
+        }
+
+        let mut instances: Vec<&str> = config.instances_of(&name).collect();
+        instances.sort_unstable();
+        for instance in instances {
+            result.push(DiscoveredModule {
+                name: instance.to_owned(),
+                file: file.clone(),
+                enabled: config.enabled(instance),
+                refused: refused.clone(),
+            });
         }
 
         let enabled = config.enabled(&name);
         result.push(DiscoveredModule {
             name,
-            path,
+            file,
             enabled,
+            refused,
         });
     }
 
+    for name in config.modules.keys() {
+        if let (base, Some(_)) = split_instance(name)
+            && !result.iter().any(|m| m.name == base)
+        {
+            tracing::warn!(module = %name, "instance of unknown module");
+        }
+    }
+
     result
 }
@@ -2,5 +2,6 @@ pub use libloading;
 pub mod config;
 pub mod discovery;
 pub mod loader;
+pub mod trust;
 pub mod watcher;
 pub mod xdg;
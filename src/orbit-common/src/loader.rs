@@ -1,23 +1,181 @@
-use std::path::Path;
+use std::{
+    ffi::CString,
+    fs::{File, Metadata},
+    io::Write,
+    os::{
+        fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd},
+        unix::{ffi::OsStrExt, fs::FileExt},
+    },
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use libloading::{Library, Symbol};
 
+/// A module file held open from discovery until it is loaded.
+///
+/// Every check is done with `fstat` on this descriptor and the library is
+/// `dlopen`ed through `/proc/self/fd/N`, so what gets mapped is the file that
+/// was checked, no matter what happens to `path` in between. A file verified
+/// against `trusted_modules` is replaced by a [sealed](Self::sealed) copy of
+/// the verified bytes, since the file itself can still be rewritten in place.
+/// Clones share the descriptor (instances of one module load the same file).
+#[derive(Debug, Clone)]
+pub struct ModuleFile {
+    path: PathBuf,
+    file: Arc<File>,
+}
+
+impl ModuleFile {
+    /// Open `name` relative to the directory `dir` without following a
+    /// symlink in its last component. `path` is only kept for messages.
+    ///
+    /// Opened non-blocking so a FIFO named like a module can't hang
+    /// discovery, and refused unless it is a regular file.
+    pub(crate) fn open_at(dir: BorrowedFd<'_>, name: &Path, path: PathBuf) -> Result<Self, String> {
+        let fd = openat(
+            dir,
+            name,
+            libc::O_RDONLY | libc::O_NOFOLLOW | libc::O_NONBLOCK,
+        )
+        .map_err(|e| format!("could not open {}: {e}", path.display()))?;
+        let file = File::from(fd);
+
+        let meta = file
+            .metadata()
+            .map_err(|e| format!("could not stat {}: {e}", path.display()))?;
+        if !meta.is_file() {
+            return Err(format!("{} is not a regular file", path.display()));
+        }
+
+        // Blocking again for everything after discovery.
+        let raw = file.as_raw_fd();
+        let flags = unsafe { libc::fcntl(raw, libc::F_GETFL) };
+        if flags < 0 || unsafe { libc::fcntl(raw, libc::F_SETFL, flags & !libc::O_NONBLOCK) } < 0 {
+            return Err(format!(
+                "could not set flags on {}: {}",
+                path.display(),
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        Ok(Self {
+            path,
+            file: Arc::new(file),
+        })
+    }
+
+    /// A sealed in-memory copy of `bytes` standing in for this file, so the
+    /// library loaded later is exactly the bytes that were checked.
+    pub(crate) fn sealed(&self, bytes: &[u8]) -> Result<Self, String> {
+        let err = |e: std::io::Error| format!("could not copy {}: {e}", self.path.display());
+
+        let name = c"orbit-module";
+        let fd = unsafe {
+            libc::memfd_create(name.as_ptr(), libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING)
+        };
+        if fd < 0 {
+            return Err(err(std::io::Error::last_os_error()));
+        }
+        // SAFETY: `memfd_create` just returned this descriptor and nothing else owns it.
+        let mut file = File::from(unsafe { OwnedFd::from_raw_fd(fd) });
+        file.write_all(bytes).map_err(err)?;
+
+        let seals =
+            libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_WRITE | libc::F_SEAL_SEAL;
+        if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_ADD_SEALS, seals) } < 0 {
+            return Err(err(std::io::Error::last_os_error()));
+        }
+
+        Ok(Self {
+            path: self.path.clone(),
+            file: Arc::new(file),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read the whole file through the open descriptor. Positional reads, so
+    /// clones sharing the descriptor don't disturb each other.
+    pub fn read_all(&self) -> Result<Vec<u8>, String> {
+        let mut buf = Vec::with_capacity(self.metadata()?.len() as usize);
+        let mut chunk = [0u8; 64 * 1024];
+        loop {
+            let n = self
+                .file
+                .read_at(&mut chunk, buf.len() as u64)
+                .map_err(|e| format!("could not read {}: {e}", self.path.display()))?;
+            if n == 0 {
+                return Ok(buf);
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    /// `fstat` on the open descriptor.
+    pub fn metadata(&self) -> Result<Metadata, String> {
+        self.file
+            .metadata()
+            .map_err(|e| format!("could not stat {}: {e}", self.path.display()))
+    }
+}
+
+impl AsFd for ModuleFile {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.file.as_fd()
+    }
+}
+
+/// `openat(2)` with `O_CLOEXEC` always set.
+pub(crate) fn openat(dir: BorrowedFd<'_>, name: &Path, flags: i32) -> std::io::Result<OwnedFd> {
+    let name = CString::new(name.as_os_str().as_bytes())
+        .map_err(|_| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+    let fd = unsafe { libc::openat(dir.as_raw_fd(), name.as_ptr(), flags | libc::O_CLOEXEC) };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // SAFETY: `openat` just returned this descriptor and nothing else owns it.
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
 /// A loaded dynamic library with a safe symbol-resolution helper.
 ///
 /// The library remains open (and its code mapped) for the lifetime of this
 /// struct.  Dropping it unmaps the library — callers must ensure no function
 /// pointers obtained from it are still in use.
 pub struct LibraryHandle {
+    // Dropped before `_file`, so the descriptor the library was opened through
+    // can't be reused while it is mapped.
     lib: Library,
+    _file: ModuleFile,
 }
 
 impl LibraryHandle {
-    // FIX: TOCTOU, use file handle instead
-    /// Open `path` with `dlopen`.
-    pub fn open(path: &Path) -> Result<Self, String> {
-        let lib = unsafe { Library::new(path) }
-            .map_err(|e| format!("could not load {}: {e}", path.display()))?;
-        Ok(Self { lib })
+    /// `dlopen` the already opened `file` through `/proc/self/fd`.
+    ///
+    /// The loader hands back a library it already has mapped under the same
+    /// path, and that path only names the descriptor number. So loading is
+    /// refused if something is still mapped under it, like a library that
+    /// stayed resident after its descriptor was closed.
+    pub fn open(file: &ModuleFile) -> Result<Self, String> {
+        let fd_path = format!("/proc/self/fd/{}", file.as_fd().as_raw_fd());
+        let loaded = unsafe {
+            libloading::os::unix::Library::open(Some(&fd_path), libc::RTLD_LAZY | libc::RTLD_NOLOAD)
+        };
+        if loaded.is_ok() {
+            return Err(format!(
+                "could not load {}: {fd_path} still maps an earlier library",
+                file.path().display()
+            ));
+        }
+        let lib = unsafe { Library::new(&fd_path) }
+            .map_err(|e| format!("could not load {}: {e}", file.path().display()))?;
+        Ok(Self {
+            lib,
+            _file: file.clone(),
+        })
     }
 
     /// Resolve a symbol by its null-terminated C name and copy the function
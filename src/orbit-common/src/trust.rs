@@ -0,0 +1,109 @@
+use std::fs;
+
+use ed25519_dalek::{Signature, VerifyingKey};
+use sha2::{Digest, Sha256};
+use yaml_serde::Value;
+
+use crate::loader::ModuleFile;
+
+/// `trusted_modules:` allowlist for user modules.
+///
+/// ```yaml
+/// trusted_modules:
+///   sha256:
+///     - 9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08
+///   keys:
+///     - 3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c
+/// ```
+///
+/// A module is trusted when the SHA-256 of its `.so` is listed, or when a
+/// detached `<file>.sig` next to it holds a valid ed25519 signature over the
+/// `.so` by one of `keys`. Digests, keys and hex signatures are hex encoded; a
+/// `.sig` may also hold the raw 64 signature bytes.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct TrustedModules {
+    pub sha256: Vec<[u8; 32]>,
+    pub keys: Vec<VerifyingKey>,
+}
+
+fn parse_hex_list<const N: usize>(root: &Value, key: &str) -> Result<Vec<[u8; N]>, String> {
+    let Some(v) = root.get(key) else {
+        return Ok(Vec::new());
+    };
+    let Some(items) = v.as_sequence() else {
+        return Err(format!("trusted_modules.{key} must be a list"));
+    };
+
+    let mut out = Vec::with_capacity(items.len());
+    for (i, item) in items.iter().enumerate() {
+        let mut bytes = [0u8; N];
+        item.as_str()
+            .and_then(|s| hex::decode_to_slice(s.trim(), &mut bytes).ok())
+            .ok_or_else(|| format!("trusted_modules.{key}[{i}] is not {N} hex-encoded bytes"))?;
+        out.push(bytes);
+    }
+    Ok(out)
+}
+
+impl TrustedModules {
+    pub fn from_value(v: &Value) -> Result<Self, String> {
+        if !v.is_mapping() {
+            return Err("trusted_modules must be a mapping with `sha256` and/or `keys`".into());
+        }
+
+        let sha256 = parse_hex_list::<32>(v, "sha256")?;
+        let keys = parse_hex_list::<32>(v, "keys")?
+            .into_iter()
+            .enumerate()
+            .map(|(i, k)| {
+                VerifyingKey::from_bytes(&k)
+                    .map_err(|_| format!("trusted_modules.keys[{i}] is not an ed25519 public key"))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { sha256, keys })
+    }
+
+    /// When `file` is allowlisted by digest or carries a valid signature,
+    /// a sealed copy of the bytes that were verified, to be loaded in its
+    /// place. Otherwise the reason it is refused.
+    pub fn verify(&self, file: &ModuleFile) -> Result<ModuleFile, String> {
+        let bytes = file.read_all()?;
+
+        let digest: [u8; 32] = Sha256::digest(&bytes).into();
+        if self.sha256.contains(&digest) {
+            return file.sealed(&bytes);
+        }
+
+        let mut sig_path = file.path().as_os_str().to_owned();
+        sig_path.push(".sig");
+        let Ok(sig) = fs::read(&sig_path) else {
+            return Err(format!(
+                "not in trusted_modules (sha256 {}) and unsigned",
+                hex::encode(digest)
+            ));
+        };
+
+        let mut sig_bytes = [0u8; 64];
+        if sig.len() == sig_bytes.len() {
+            sig_bytes.copy_from_slice(&sig);
+        } else if std::str::from_utf8(&sig)
+            .ok()
+            .and_then(|s| hex::decode_to_slice(s.trim(), &mut sig_bytes).ok())
+            .is_none()
+        {
+            return Err("malformed signature file".into());
+        }
+
+        let signature = Signature::from_bytes(&sig_bytes);
+        if self
+            .keys
+            .iter()
+            .any(|key| key.verify_strict(&bytes, &signature).is_ok())
+        {
+            file.sealed(&bytes)
+        } else {
+            Err("signature does not match any trusted key".into())
+        }
+    }
+}
@@ -1,3 +1,10 @@
+use std::{
+    fmt,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
 pub const DESTINATION: &str = "io.github.orbitshell.Orbit1";
 pub const OBJECT_PATH: &str = "/io/github/orbitshell/Orbit1";
 pub const INTERFACE: &str = "io.github.orbitshell.Orbit1";
@@ -9,5 +16,85 @@ pub enum DbusEvent {
     Commands(String, std::sync::mpsc::Sender<String>),
     Toggle(String),
     Command(String, String),
+    CommandWithArgs(String, String, Vec<String>, ReplySender),
     Exit,
 }
+
+#[derive(Default)]
+struct ReplyState {
+    /// Set once, by the first answer or the last sender dropping.
+    answer: Option<Option<String>>,
+    senders: usize,
+    waker: Option<Waker>,
+}
+
+/// Channel for a single answer to a call that waits on a module, like
+/// `command_with_args`. The module may answer long after the event was
+/// handled, from wherever it keeps the sender.
+pub fn reply() -> (ReplySender, Reply) {
+    let state = Arc::new(Mutex::new(ReplyState {
+        senders: 1,
+        ..Default::default()
+    }));
+    (ReplySender(Arc::clone(&state)), Reply(state))
+}
+
+pub struct ReplySender(Arc<Mutex<ReplyState>>);
+
+impl ReplySender {
+    /// Answers the call. Only the first answer of all clones counts.
+    pub fn send(&self, answer: Option<String>) {
+        let mut state = self.0.lock().unwrap();
+        if state.answer.is_none() {
+            state.answer = Some(answer);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl Clone for ReplySender {
+    fn clone(&self) -> Self {
+        self.0.lock().unwrap().senders += 1;
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl Drop for ReplySender {
+    fn drop(&mut self) {
+        let last = {
+            let mut state = self.0.lock().unwrap();
+            state.senders -= 1;
+            state.senders == 0
+        };
+        // Nobody left to answer, so nothing was picked.
+        if last {
+            self.send(None);
+        }
+    }
+}
+
+impl fmt::Debug for ReplySender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ReplySender(..)")
+    }
+}
+
+/// Resolves to the answer, `None` if every sender was dropped without one.
+pub struct Reply(Arc<Mutex<ReplyState>>);
+
+impl Future for Reply {
+    type Output = Option<String>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<String>> {
+        let mut state = self.0.lock().unwrap();
+        match state.answer.take() {
+            Some(answer) => Poll::Ready(answer),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
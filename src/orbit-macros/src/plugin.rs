@@ -11,6 +11,7 @@ struct PluginInput {
     name: Expr,
     options: Expr,
     commands: Vec<(Expr, Expr)>,
+    arg_commands: Vec<(Expr, Expr)>,
     show_on_startup: Expr,
     persistent_state: Expr,
     pipelines: Expr,
@@ -21,6 +22,7 @@ enum Field {
     Name(Expr),
     Options(Expr),
     Commands(Vec<(Expr, Expr)>),
+    ArgCommands(Vec<(Expr, Expr)>),
     ShowOnStartup(Expr),
     PersistentState(Expr),
     Pipelines(Expr),
@@ -38,15 +40,8 @@ impl Parse for Field {
             "show_on_startup" => Ok(Field::ShowOnStartup(input.parse()?)),
             "persistent_state" => Ok(Field::PersistentState(input.parse()?)),
             "pipelines" => Ok(Field::Pipelines(input.parse()?)),
-            "commands" => {
-                let content;
-                syn::bracketed!(content in input);
-                let pairs: Punctuated<CommandPair, Token![,]> =
-                    content.parse_terminated(CommandPair::parse, Token![,])?;
-                Ok(Field::Commands(
-                    pairs.into_iter().map(|p| (p.name, p.msg)).collect(),
-                ))
-            }
+            "commands" => Ok(Field::Commands(parse_command_pairs(input)?)),
+            "arg_commands" => Ok(Field::ArgCommands(parse_command_pairs(input)?)),
             other => Err(syn::Error::new(
                 key.span(),
                 format!("unknown field `{other}` in orbit_plugin!"),
@@ -55,6 +50,14 @@ impl Parse for Field {
     }
 }
 
+fn parse_command_pairs(input: ParseStream) -> syn::Result<Vec<(Expr, Expr)>> {
+    let content;
+    syn::bracketed!(content in input);
+    let pairs: Punctuated<CommandPair, Token![,]> =
+        content.parse_terminated(CommandPair::parse, Token![,])?;
+    Ok(pairs.into_iter().map(|p| (p.name, p.msg)).collect())
+}
+
 struct CommandPair {
     name: Expr,
     msg: Expr,
@@ -80,6 +83,7 @@ impl Parse for PluginInput {
         let mut name: Option<Expr> = None;
         let mut options: Option<Expr> = None;
         let mut commands: Vec<(Expr, Expr)> = Vec::new();
+        let mut arg_commands: Vec<(Expr, Expr)> = Vec::new();
         let mut show_on_startup: Option<Expr> = None;
         let mut persistent_state: Option<Expr> = None;
         let mut pipelines: Option<Expr> = None;
@@ -90,6 +94,7 @@ impl Parse for PluginInput {
                 Field::Name(v) => name = Some(v),
                 Field::Options(v) => options = Some(v),
                 Field::Commands(v) => commands = v,
+                Field::ArgCommands(v) => arg_commands = v,
                 Field::ShowOnStartup(v) => show_on_startup = Some(v),
                 Field::PersistentState(v) => persistent_state = Some(v),
                 Field::Pipelines(v) => pipelines = Some(v),
@@ -107,6 +112,7 @@ impl Parse for PluginInput {
             name,
             options,
             commands,
+            arg_commands,
             show_on_startup: show_on_startup.unwrap_or_else(|| syn::parse_quote!(false)),
             persistent_state: persistent_state.unwrap_or_else(|| syn::parse_quote!(false)),
             pipelines: pipelines.unwrap_or_else(|| syn::parse_quote!([])),
@@ -120,14 +126,17 @@ pub fn orbit_plugin_impl(input: TokenStream) -> TokenStream {
         name,
         options,
         commands,
+        arg_commands,
         show_on_startup,
         persistent_state,
         pipelines,
     } = syn::parse_macro_input!(input as PluginInput);
 
     let cmd_names: Vec<&Expr> = commands.iter().map(|(n, _)| n).collect();
-    let cmd_names2 = cmd_names.clone();
     let cmd_msgs: Vec<&Expr> = commands.iter().map(|(_, m)| m).collect();
+    let arg_cmd_names: Vec<&Expr> = arg_commands.iter().map(|(n, _)| n).collect();
+    let arg_cmd_msgs: Vec<&Expr> = arg_commands.iter().map(|(_, m)| m).collect();
+    let all_cmd_names: Vec<&Expr> = cmd_names.iter().chain(&arg_cmd_names).copied().collect();
 
     let output = quote! {
         #[doc(hidden)]
@@ -232,15 +241,12 @@ pub fn orbit_plugin_impl(input: TokenStream) -> TokenStream {
                         message: orbit_api::ErasedMsg::new(message),
                     },
                     Batch(v) => Batch(v.into_iter().map(Self::map_sub).collect()),
+                    Topic(t) => Topic(t.map(orbit_api::ErasedMsg::new::<M>)),
+                    Background(s) => Background(::std::boxed::Box::new(Self::map_sub(*s))),
                     Stream(typed_factory) => {
                         Stream(::std::boxed::Box::new(
                             move |erased_tx: orbit_api::SubscriptionSender<orbit_api::ErasedMsg>| {
-                                let typed_tx = orbit_api::SubscriptionSender::new(
-                                    ::std::sync::Arc::new(move |msg: M| {
-                                        erased_tx.send(orbit_api::ErasedMsg::new(msg))
-                                    }),
-                                );
-                                typed_factory(typed_tx)
+                                typed_factory(erased_tx.map(orbit_api::ErasedMsg::new::<M>))
                             },
                         ))
                     }
@@ -265,6 +271,7 @@ pub fn orbit_plugin_impl(input: TokenStream) -> TokenStream {
                     RedrawModule => RedrawModule,
                     ExitModule => ExitModule,
                     ExitOrbit => ExitOrbit,
+                    Publish(p) => Publish(p),
                 }
             }
         }
@@ -352,11 +359,11 @@ pub fn orbit_plugin_impl(input: TokenStream) -> TokenStream {
                 &mut self,
                 ctl: &mut orbit_api::OrbitCtl<'_>,
                 tid: Option<orbit_api::ui::graphics::TargetId>,
-                key: &'static str,
+                broadcast: &orbit_api::Broadcast,
             ) -> orbit_api::Task<orbit_api::ErasedMsg> {
                 Self::map_task(
                     <#module_ty as orbit_api::OrbitModule>::on_broadcast(
-                        self.inner_mut(), ctl, tid, key,
+                        self.inner_mut(), ctl, tid, broadcast,
                     ),
                 )
             }
@@ -382,6 +389,24 @@ pub fn orbit_plugin_impl(input: TokenStream) -> TokenStream {
                 }
             }
 
+            fn command_args_message(
+                &self,
+                command: &str,
+                args: orbit_api::CommandArgs,
+            ) -> ::std::option::Option<orbit_api::ErasedMsg> {
+                match command {
+                    #(
+                        #arg_cmd_names => {
+                            ::std::option::Option::Some(orbit_api::ErasedMsg::new((#arg_cmd_msgs)(args)))
+                        }
+                    )*
+                    _ => {
+                        let _ = args;
+                        ::std::option::Option::None
+                    }
+                }
+            }
+
             fn subscriptions(&self) -> orbit_api::Subscription<orbit_api::ErasedMsg> {
                 Self::map_sub::<<#module_ty as orbit_api::OrbitModule>::Message>(
                     <#module_ty as orbit_api::OrbitModule>::subscriptions(self.inner_ref()),
@@ -395,7 +420,7 @@ pub fn orbit_plugin_impl(input: TokenStream) -> TokenStream {
             let wrapper = __Wrapper {
                 manifest: orbit_api::runtime::Manifest {
                     name: #name,
-                    commands: &[#(#cmd_names2),*],
+                    commands: &[#(#all_cmd_names),*],
                     options: #options,
                     show_on_startup: #show_on_startup,
                     persistent_state: #persistent_state,
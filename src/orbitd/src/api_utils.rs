@@ -1,4 +1,6 @@
-use orbit_api::{BoxFuture, BoxStreamFactory, ErasedMsg, Subscription, Task};
+use orbit_api::{
+    BoxFuture, BoxStreamFactory, ErasedMsg, Published, Subscription, Task, TopicSubscription,
+};
 
 pub enum Action {
     ExitOrbit,
@@ -14,6 +16,7 @@ pub struct UnraveledTask {
     redraw_module: bool,
 
     pub tasks: Option<Vec<BoxFuture<ErasedMsg>>>,
+    pub published: Vec<Published>,
 }
 
 impl UnraveledTask {
@@ -59,6 +62,7 @@ pub fn unravel_task(shown: bool, t: Task<ErasedMsg>) -> (UnraveledTask, bool) {
             Task::ExitModule => ut.exit_module = true,
             Task::ExitOrbit => ut.exit_orbit = true,
             Task::Spawn(pin) => ut.tasks.get_or_insert_default().push(pin),
+            Task::Publish(msg) => ut.published.push(msg),
         }
     }
 
@@ -72,11 +76,23 @@ pub fn unravel_task(shown: bool, t: Task<ErasedMsg>) -> (UnraveledTask, bool) {
 pub struct UnraveledSub {
     pub subs: Vec<Subscription<ErasedMsg>>,
     pub streams: Vec<BoxStreamFactory<ErasedMsg>>,
+    pub topics: Vec<TopicSubscription<ErasedMsg>>,
 }
 
-pub fn unravel_sub(s: orbit_api::Subscription<ErasedMsg>) -> UnraveledSub {
-    fn unravel_sub_internal(s: orbit_api::Subscription<ErasedMsg>, us: &mut UnraveledSub) {
+/// Split `s` into what runs while shown and what runs in the background.
+pub fn unravel_sub(s: orbit_api::Subscription<ErasedMsg>) -> (UnraveledSub, UnraveledSub) {
+    fn unravel_sub_internal(
+        s: orbit_api::Subscription<ErasedMsg>,
+        background: bool,
+        shown_us: &mut UnraveledSub,
+        background_us: &mut UnraveledSub,
+    ) {
         use orbit_api::Subscription::*;
+        let us = if background {
+            &mut *background_us
+        } else {
+            &mut *shown_us
+        };
         match s {
             None => (),
             Stream(factory) => {
@@ -84,14 +100,17 @@ pub fn unravel_sub(s: orbit_api::Subscription<ErasedMsg>) -> UnraveledSub {
             }
             Batch(v) => {
                 for child in v {
-                    unravel_sub_internal(child, us);
+                    unravel_sub_internal(child, background, shown_us, background_us);
                 }
             }
+            Topic(topic) => us.topics.push(topic),
+            Background(child) => unravel_sub_internal(*child, true, shown_us, background_us),
             other => us.subs.push(other),
         }
     }
 
-    let mut usub = UnraveledSub::default();
-    unravel_sub_internal(s, &mut usub);
-    usub
+    let mut shown = UnraveledSub::default();
+    let mut background = UnraveledSub::default();
+    unravel_sub_internal(s, false, &mut shown, &mut background);
+    (shown, background)
 }
@@ -4,10 +4,16 @@ use std::{
     time::Duration,
 };
 
+use async_io::Timer;
 use calloop::channel as loop_channel;
+use futures_lite::future;
 use orbit_dbus::{DESTINATION, DbusEvent, OBJECT_PATH};
 use zbus::{blocking::connection::Builder, interface};
 
+/// How long `command_with_args` waits on the module before giving up, so a
+/// module that never answers can't keep its caller waiting forever.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(300);
+
 pub struct OrbitdServer {
     tx: loop_channel::Sender<DbusEvent>,
     handle: Option<JoinHandle<()>>,
@@ -127,6 +133,34 @@ impl OrbitIface {
             .tx
             .send(DbusEvent::Command(module.to_string(), command.to_string()));
     }
+    /// Like `command`, but waits for the module to answer. `false` if it
+    /// didn't, like when a picker was dismissed, or not within
+    /// `REPLY_TIMEOUT`.
+    async fn command_with_args(
+        &self,
+        module: &str,
+        command: &str,
+        args: Vec<String>,
+    ) -> (bool, String) {
+        tracing::info!(module = %module, command = %command, args = args.len(), "command with args");
+        let (reply_tx, reply) = orbit_dbus::reply();
+        let _ = self.tx.send(DbusEvent::CommandWithArgs(
+            module.to_string(),
+            command.to_string(),
+            args,
+            reply_tx,
+        ));
+
+        let timeout = async {
+            Timer::after(REPLY_TIMEOUT).await;
+            tracing::warn!(module = %module, command = %command, "no answer in time, giving up");
+            None
+        };
+        match future::or(reply, timeout).await {
+            Some(answer) => (true, answer),
+            None => (false, String::new()),
+        }
+    }
     fn exit(&self) {
         tracing::warn!("exit requested");
         let _ = self.tx.send(DbusEvent::Exit);
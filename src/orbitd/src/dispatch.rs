@@ -1,6 +1,5 @@
 use std::{
     sync::Arc,
-    thread::JoinHandle,
     time::{Duration, SystemTime},
 };
 
@@ -8,18 +7,26 @@ use calloop::{
     LoopHandle, RegistrationToken, channel as loop_channel,
     timer::{TimeoutAction, Timer},
 };
-use orbit_api::{BoxStreamFactory, ErasedMsg, SendError, Subscription, SubscriptionSender};
+use orbit_api::{
+    BoxStreamFactory, CloseHandle, ErasedMsg, SendError, Subscription, SubscriptionSender,
+};
 use ui::sctk::state::SctkState;
 
 use crate::{
     api_utils::{self, UnraveledTask},
     event::{self, RuntimeSender},
+    executor::{Executor, Scope},
     module::ModuleId,
 };
 
-pub struct StreamHandle {
-    pub rx_token: RegistrationToken,
-    pub thread: JoinHandle<()>,
+/// Closes a stream's senders when its future is dropped, which is how the
+/// executor cancels it, so clones held outside the future notice too.
+struct CloseOnDrop(CloseHandle);
+
+impl Drop for CloseOnDrop {
+    fn drop(&mut self) {
+        self.0.close();
+    }
 }
 
 fn handle_timer(
@@ -111,9 +118,10 @@ pub fn handle_subs(
             orbit_api::Subscription::SyncedTimeout { message, after } => tokens.push(
                 handle_timer_synced(tx, loop_handle, *mid, message, after, false),
             ),
-            orbit_api::Subscription::Batch(_) | orbit_api::Subscription::Stream(_) => {
-                unreachable!()
-            }
+            orbit_api::Subscription::Batch(_)
+            | orbit_api::Subscription::Stream(_)
+            | orbit_api::Subscription::Topic(_)
+            | orbit_api::Subscription::Background(_) => unreachable!(),
             orbit_api::Subscription::None => (),
         }
     }
@@ -123,8 +131,10 @@ pub fn handle_streams(
     streams: Vec<BoxStreamFactory<ErasedMsg>>,
     tx: &RuntimeSender,
     loop_handle: &mut LoopHandle<SctkState>,
+    executor: &mut Executor,
+    scope: Scope,
     mid: &ModuleId,
-    handles: &mut Vec<StreamHandle>,
+    tokens: &mut Vec<RegistrationToken>,
 ) {
     for factory in streams {
         let ui_tx = tx.clone();
@@ -148,14 +158,16 @@ pub fn handle_streams(
             stream_tx.send(msg).map_err(|_| SendError::Disconnected)
         }));
 
+        let guard = CloseOnDrop(sender.close_handle());
         let future = factory(sender);
-
-        let thread = std::thread::Builder::new()
-            .name(format!("orbit-stream-{}", mid.0))
-            .spawn(move || futures_lite::future::block_on(future))
-            .expect("spawn stream thread");
-
-        handles.push(StreamHandle { rx_token, thread });
+        executor.spawn(
+            scope,
+            Box::pin(async move {
+                let _guard = guard;
+                future.await
+            }),
+        );
+        tokens.push(rx_token);
     }
 }
 
@@ -164,9 +176,20 @@ pub fn handle_task(
     mid: &ModuleId,
     tx: &RuntimeSender,
     dispatch_tx: &loop_channel::Sender<(ModuleId, ErasedMsg)>,
-    pending_threads: &mut Vec<JoinHandle<()>>,
+    executor: &mut Executor,
 ) {
     if let Some(ut) = utask.as_mut() {
+        // Delivered even when the module exits right after, e.g. a lockscreen
+        // announcing it unlocked.
+        for message in ut.published.drain(..) {
+            tx.send(event::Event::Ui(event::Ui::Orbit(
+                event::OrbitMessage::Publish {
+                    from: *mid,
+                    message,
+                },
+            )));
+        }
+
         match ut.action() {
             api_utils::Action::ExitOrbit => {
                 tx.send(event::Event::Dbus(orbit_dbus::DbusEvent::Exit));
@@ -186,14 +209,13 @@ pub fn handle_task(
             for task in pending {
                 let mid = *mid;
                 let result_tx = dispatch_tx.clone();
-                let thread = std::thread::Builder::new()
-                    .name(format!("orbit-task-{}", mid.0))
-                    .spawn(move || {
-                        let msg = futures_lite::future::block_on(task);
+                executor.spawn(
+                    Scope::Shown(mid),
+                    Box::pin(async move {
+                        let msg = task.await;
                         let _ = result_tx.send((mid, msg.clone_for_send()));
-                    })
-                    .expect("spawn task thread");
-                pending_threads.push(thread);
+                    }),
+                );
             }
         }
     }
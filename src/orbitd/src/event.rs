@@ -1,6 +1,6 @@
 use std::sync::mpsc;
 
-use orbit_api::{ErasedMsg, OutputTag};
+use orbit_api::{BroadcastKind, ErasedMsg, OutputTag, Published};
 use orbit_common::config::ConfigEvent;
 use orbit_dbus::DbusEvent;
 use ui::sctk::SctkEvent;
@@ -49,6 +49,11 @@ pub enum OrbitMessage {
         from: ModuleId,
         scope: Option<OutputTag>,
         key: &'static str,
+        kind: BroadcastKind,
+    },
+    Publish {
+        from: ModuleId,
+        message: Published,
     },
 }
 
@@ -0,0 +1,72 @@
+use std::{collections::HashMap, sync::Arc, thread};
+
+use async_executor::Task;
+use orbit_api::BoxFuture;
+
+use crate::module::ModuleId;
+
+/// What a future's lifetime is tied to.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Scope {
+    /// Cancelled when the module is hidden.
+    Shown(ModuleId),
+    /// Cancelled only when the module is unloaded (background subscriptions).
+    Loaded(ModuleId),
+}
+
+/// Shared worker pool driving module futures (`Task::Spawn` and subscription
+/// streams) instead of one OS thread per future.
+///
+/// Every future is tracked per [`Scope`] through its `async_executor::Task`
+/// handle. Dropping a handle cancels the future at its next `.await`, which is
+/// how hiding a module stops the work it started.
+///
+/// A future that blocks holds one of the few workers, and can't be cancelled
+/// until it yields, so modules run blocking work through `Task::blocking` or
+/// `orbit_api::unblock`, which use a separate, growing pool.
+pub struct Executor {
+    ex: Arc<async_executor::Executor<'static>>,
+    running: HashMap<Scope, Vec<Task<()>>>,
+}
+
+impl Executor {
+    pub fn new() -> Self {
+        let ex = Arc::new(async_executor::Executor::new());
+        let workers = thread::available_parallelism().map_or(2, |n| n.get().clamp(2, 4));
+        for i in 0..workers {
+            let ex = Arc::clone(&ex);
+            thread::Builder::new()
+                .name(format!("orbit-worker-{i}"))
+                .spawn(move || futures_lite::future::block_on(ex.run(std::future::pending::<()>())))
+                .expect("spawn worker thread");
+        }
+
+        Self {
+            ex,
+            running: HashMap::new(),
+        }
+    }
+
+    /// Run `fut` on the pool until it completes or `scope` is cancelled.
+    pub fn spawn(&mut self, scope: Scope, fut: BoxFuture<()>) {
+        let task = self.ex.spawn(fut);
+        self.running.entry(scope).or_default().push(task);
+    }
+
+    /// Cancel everything still in flight for `scope`.
+    pub fn cancel(&mut self, scope: Scope) {
+        self.running.remove(&scope);
+    }
+
+    pub fn cancel_all(&mut self) {
+        self.running.clear();
+    }
+
+    /// Forget handles of futures that already completed.
+    pub fn reap(&mut self) {
+        self.running.retain(|_, tasks| {
+            tasks.retain(|t| !t.is_finished());
+            !tasks.is_empty()
+        });
+    }
+}
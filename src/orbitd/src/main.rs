@@ -12,7 +12,7 @@ use ui::{
     sctk::{RawWaylandHandles, SctkEvent, state::SctkState},
 };
 
-use orbit_api::{Engine, ErasedMsg};
+use orbit_api::{CommandArgs, Engine, ErasedMsg};
 use orbit_common::{config, watcher::ConfigWatcher};
 use orbit_dbus::DbusEvent;
 
@@ -32,6 +32,7 @@ mod dbus;
 mod dialog;
 mod dispatch;
 mod event;
+mod executor;
 mod module;
 mod module_manager;
 mod sctk;
@@ -143,7 +144,6 @@ impl<'a> Orbit<'a> {
         reply
     }
 
-    // TODO: subscription streams should be running while loaded not only when toggled/shown.
     fn run(&mut self) {
         let mut event_loop: EventLoop<SctkState> = EventLoop::try_new().expect("err");
         let _ = WaylandSource::new(self.sctk.conn.clone(), self.sctk.take_event_queue())
@@ -191,6 +191,8 @@ impl<'a> Orbit<'a> {
         };
 
         let runtime_tx = RuntimeSender::new(self.tx.clone(), event_loop.get_signal());
+        self.module_manager
+            .start_background_subscriptions(&runtime_tx, &mut event_loop.handle());
         self.module_manager.realize_toggled_modules(
             &mut self.engine,
             &mut self.sctk,
@@ -291,7 +293,12 @@ impl<'a> Orbit<'a> {
                                         }
                                     }
                                 }
-                                event::OrbitMessage::BroadCast { from, scope, key } => {
+                                event::OrbitMessage::BroadCast {
+                                    from,
+                                    scope,
+                                    key,
+                                    kind,
+                                } => {
                                     self.module_manager.route_broadcast(
                                         &mut self.engine,
                                         &runtime_tx,
@@ -299,8 +306,13 @@ impl<'a> Orbit<'a> {
                                         from,
                                         scope,
                                         key,
+                                        kind,
                                     );
                                 }
+                                event::OrbitMessage::Publish { from, message } => {
+                                    self.module_manager
+                                        .route_message(&runtime_tx, from, &message);
+                                }
                             },
                             event::Ui::Sctk(sctk_event) => {
                                 if let Some(sid) = sctk_event.surface_id() {
@@ -396,6 +408,10 @@ impl<'a> Orbit<'a> {
                                 &self.config_path,
                             ) {
                                 Ok(_) => {
+                                    self.module_manager.start_background_subscriptions(
+                                        &runtime_tx,
+                                        &mut event_loop.handle(),
+                                    );
                                     self.module_manager.realize_toggled_modules(
                                         &mut self.engine,
                                         &mut self.sctk,
@@ -417,6 +433,11 @@ impl<'a> Orbit<'a> {
 
                             for mid in self.module_manager.module_ids_sorted() {
                                 let module = self.module_manager.module(mid).expect("just found");
+                                if let Some(reason) = &module.refused {
+                                    writeln!(reply, "\t{} (refused: {})", module.name, reason)
+                                        .unwrap();
+                                    continue;
+                                }
                                 let loaded = if module.is_loaded() {
                                     "loaded"
                                 } else {
@@ -496,6 +517,57 @@ impl<'a> Orbit<'a> {
                                 SctkEvent::message(message),
                             )));
                         }
+                        DbusEvent::CommandWithArgs(module_name, command_name, args, reply) => {
+                            let Some((mid, module)) = self
+                                .module_manager
+                                .find_by_name(&module_name)
+                                .filter(|(_, m)| m.is_loaded())
+                            else {
+                                // Dropping `reply` tells the caller nothing came of it.
+                                tracing::warn!(module = %module_name, command = %command_name, "command for unknown or unloaded module");
+                                continue;
+                            };
+
+                            // Commands without arguments run as usual and answer right away.
+                            if let Some(message) = module.as_ref().command_message(&command_name) {
+                                runtime_tx.send(Event::Ui(event::Ui::Module(
+                                    mid,
+                                    SctkEvent::message(message),
+                                )));
+                                reply.send(Some(String::new()));
+                                continue;
+                            }
+
+                            let args = CommandArgs::new(args, move |answer| reply.send(answer));
+                            let Some(message) =
+                                module.as_ref().command_args_message(&command_name, args)
+                            else {
+                                tracing::warn!(module = %module_name, command = %command_name, "unknown module command");
+                                continue;
+                            };
+
+                            // The caller waits on an answer, so the module has to be up
+                            // to give one.
+                            let is_lock = matches!(
+                                module.as_ref().manifest().options,
+                                ui::sctk::Options::Lock(_)
+                            );
+                            if !module.toggled && !is_lock {
+                                self.module_manager.realize_module(
+                                    &mut self.engine,
+                                    &mut self.sctk,
+                                    &runtime_tx,
+                                    &mut event_loop.handle(),
+                                    &self.config,
+                                    &mid,
+                                );
+                            }
+
+                            runtime_tx.send(Event::Ui(event::Ui::Module(
+                                mid,
+                                SctkEvent::message(message),
+                            )));
+                        }
                         DbusEvent::Exit => {
                             orbit_closed = true;
                             event_loop.get_signal().stop();
@@ -518,7 +590,8 @@ impl<'a> Orbit<'a> {
                                 },
                             ) in instructions
                             {
-                                let Some(mid) = self.module_manager.find_id_by_name(&name) else {
+                                let Some(mid) = self.module_manager.find_or_add_instance(&name)
+                                else {
                                     errors.push(format!("Module {} could not be found.", &name));
                                     continue;
                                 };
@@ -530,6 +603,10 @@ impl<'a> Orbit<'a> {
                                         &mut event_loop.handle(),
                                         &mid,
                                     );
+                                    self.module_manager.remove_background_subscriptions(
+                                        &mut event_loop.handle(),
+                                        &mid,
+                                    );
                                     if let Some(module) = self.module_manager.module_mut(mid) {
                                         module.unload(&mut self.engine);
                                     }
@@ -548,6 +625,11 @@ impl<'a> Orbit<'a> {
                                 }
 
                                 if should_realize {
+                                    self.module_manager.add_background_subscriptions(
+                                        &runtime_tx,
+                                        &mut event_loop.handle(),
+                                        &mid,
+                                    );
                                     let show_on_startup = self
                                         .module_manager
                                         .module(mid)
@@ -605,6 +687,18 @@ impl<'a> Orbit<'a> {
                                         );
                                         runtime_tx.send(Event::Ui(event::Ui::ForceRedraw(mid)));
                                     }
+
+                                    // Background subscriptions outlive the rebuild, so
+                                    // both ways they are redone with the new config.
+                                    self.module_manager.remove_background_subscriptions(
+                                        &mut event_loop.handle(),
+                                        &mid,
+                                    );
+                                    self.module_manager.add_background_subscriptions(
+                                        &runtime_tx,
+                                        &mut event_loop.handle(),
+                                        &mid,
+                                    );
                                 }
                             }
 
@@ -634,7 +728,7 @@ impl<'a> Orbit<'a> {
                 self.error_dialog.render(&mut self.engine);
             }
 
-            self.module_manager.reap_threads();
+            self.module_manager.reap_tasks();
         }
 
         self.d_server.stop();
@@ -1,26 +1,26 @@
-use std::{
-    path::{Path, PathBuf},
-    ptr::NonNull,
-};
+use std::ptr::NonNull;
 
 use orbit_api::{Engine, runtime::OrbitModuleDyn};
-use orbit_common::loader::LibraryHandle;
+use orbit_common::loader::{LibraryHandle, ModuleFile};
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub struct ModuleId(pub u32);
 
 pub struct ModuleInfo {
     pub name: String,
-    pub path: PathBuf,
+    pub file: ModuleFile,
+    /// Set when discovery refused the file; such a module is never loaded.
+    pub refused: Option<String>,
     pub inner: Option<Module>,
     pub toggled: bool,
 }
 
 impl ModuleInfo {
-    pub fn new(name: String, path: PathBuf) -> Self {
+    pub fn new(name: String, file: ModuleFile, refused: Option<String>) -> Self {
         Self {
             name,
-            path,
+            file,
+            refused,
             inner: None,
             toggled: false,
         }
@@ -31,8 +31,11 @@ impl ModuleInfo {
     }
 
     pub fn ensure_loaded(&mut self) -> Result<(), String> {
+        if let Some(reason) = &self.refused {
+            return Err(format!("module {} refused: {reason}", self.name));
+        }
         if self.inner.is_none() {
-            self.inner = Some(Module::new(&self.path)?);
+            self.inner = Some(Module::new(&self.file)?);
         }
 
         Ok(())
@@ -70,9 +73,9 @@ impl Drop for Module {
 }
 
 impl Module {
-    pub fn new(path: &Path) -> Result<Self, String> {
-        tracing::debug!(path = %path.display(), "loading");
-        let library = LibraryHandle::open(path)?;
+    pub fn new(file: &ModuleFile) -> Result<Self, String> {
+        tracing::debug!(path = %file.path().display(), "loading");
+        let library = LibraryHandle::open(file)?;
 
         let create: CreateFn = unsafe { library.get_fn(b"orbit_module_create\0")? };
         let destroy: DestroyFn = unsafe { library.get_fn(b"orbit_module_destroy\0")? };
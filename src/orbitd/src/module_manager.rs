@@ -1,8 +1,13 @@
-use std::thread::JoinHandle;
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
 
 use calloop::{LoopHandle, RegistrationToken, channel as loop_channel};
-use orbit_api::{Engine, ErasedMsg, OrbitCtl, OutputInfo, OutputTag, ResourceManager};
+use orbit_api::{
+    Broadcast, BroadcastKind, Engine, ErasedMsg, OrbitCtl, OutputInfo, OutputTag, Published,
+    ResourceManager, TopicSubscription,
+};
 use orbit_common::config::Config;
 use ui::theme::Theme;
 use ui::{
@@ -11,8 +16,8 @@ use ui::{
     sctk::{SctkEvent, SurfaceId, state::SctkState},
 };
 
-use crate::dispatch::StreamHandle;
 use crate::event::RuntimeSender;
+use crate::executor::{Executor, Scope};
 use crate::sctk;
 use crate::{
     api_utils::{self, UnraveledTask},
@@ -20,18 +25,24 @@ use crate::{
     sctk::SctkApp,
 };
 
+type Interest = HashMap<ModuleId, HashSet<&'static str>>;
+
 pub struct ModuleManager {
     modules: HashMap<ModuleId, ModuleInfo>,
     sub_tokens: HashMap<ModuleId, Vec<RegistrationToken>>,
-    dispatch_tokens: HashMap<ModuleId, Vec<StreamHandle>>,
+    dispatch_tokens: HashMap<ModuleId, Vec<RegistrationToken>>,
+    background_tokens: HashMap<ModuleId, Vec<RegistrationToken>>,
+    topics: HashMap<Scope, Vec<TopicSubscription<ErasedMsg>>>,
     by_module: HashMap<ModuleId, Vec<TargetId>>,
     by_surface: HashMap<SurfaceId, (TargetId, ModuleId)>,
     by_target: HashMap<TargetId, (SurfaceId, ModuleId)>,
 
     resources: ResourceManager,
+    /// Keys each module has leased, the only ones it gets broadcasts for.
+    interest: Interest,
     target_output: HashMap<TargetId, OutputInfo>,
 
-    pending_threads: Vec<JoinHandle<()>>,
+    executor: Executor,
     pending_surfaces: HashMap<SurfaceId, ModuleId>,
 }
 
@@ -48,12 +59,15 @@ impl ModuleManager {
             modules,
             sub_tokens: HashMap::new(),
             dispatch_tokens: HashMap::new(),
+            background_tokens: HashMap::new(),
+            topics: HashMap::new(),
             by_module: HashMap::with_capacity(modules_len),
             by_surface: HashMap::with_capacity(modules_len),
             by_target: HashMap::with_capacity(modules_len),
             resources: ResourceManager::default(),
+            interest: HashMap::new(),
             target_output: HashMap::new(),
-            pending_threads: Vec::new(),
+            executor: Executor::new(),
             pending_surfaces: HashMap::new(),
         })
     }
@@ -90,6 +104,24 @@ impl ModuleManager {
             .map(|(mid, m)| (*mid, m))
     }
 
+    /// Resolve `name`, registering it on the fly when it is a not yet known
+    /// instance (`bar@top`) of a discovered module. Lets instances declared in
+    /// a hot-reloaded config start without a full rediscovery.
+    pub fn find_or_add_instance(&mut self, name: &str) -> Option<ModuleId> {
+        if let Some(mid) = self.find_id_by_name(name) {
+            return Some(mid);
+        }
+
+        let (base, Some(_)) = orbit_common::config::split_instance(name) else {
+            return None;
+        };
+        let base = &self.find_by_name(base)?.1;
+        let info = ModuleInfo::new(name.to_owned(), base.file.clone(), base.refused.clone());
+        let mid = ModuleId(self.modules.keys().map(|id| id.0 + 1).max().unwrap_or(0));
+        self.modules.insert(mid, info);
+        Some(mid)
+    }
+
     pub fn module(&self, id: ModuleId) -> Option<&ModuleInfo> {
         self.modules.get(&id)
     }
@@ -127,8 +159,8 @@ impl ModuleManager {
         self.by_target.insert(tid, (sid, mid));
     }
 
-    pub fn reap_threads(&mut self) {
-        self.pending_threads.retain(|h| !h.is_finished());
+    pub fn reap_tasks(&mut self) {
+        self.executor.reap();
     }
 
     pub fn set_target_output(&mut self, tid: TargetId, info: OutputInfo) {
@@ -156,7 +188,7 @@ fn discover_modules(
     let discovered = orbit_common::discovery::discover_modules(config_path, config);
     let mut modules = Vec::with_capacity(prev_module_len.unwrap_or(discovered.len()));
     for d in discovered {
-        modules.push(ModuleInfo::new(d.name, d.path));
+        modules.push(ModuleInfo::new(d.name, d.file, d.refused));
     }
     Ok(modules)
 }
@@ -170,7 +202,10 @@ fn load_modules(
     let mut next_id: u32 = 0;
     for mut module in modules {
         let enabled = cfg.enabled(&module.name);
-        if enabled {
+        if enabled && module.refused.is_some() {
+            // Already warned about during discovery, keep it listed but unloaded.
+            module.toggled = false;
+        } else if enabled {
             ModuleManager::load_module(engine, cfg.get(&module.name), &mut module)?;
             module.toggled = module.as_ref().manifest().show_on_startup;
         } else {
@@ -196,15 +231,27 @@ fn output_for(
 fn emit_broadcasts(
     tx: &RuntimeSender,
     from: ModuleId,
-    dirty: Vec<(&'static str, Option<OutputTag>)>,
+    dirty: Vec<(&'static str, Option<OutputTag>, BroadcastKind)>,
 ) {
-    for (key, scope) in dirty {
+    for (key, scope, kind) in dirty {
         tx.send(crate::event::Event::Ui(crate::event::Ui::Orbit(
-            crate::event::OrbitMessage::BroadCast { from, scope, key },
+            crate::event::OrbitMessage::BroadCast {
+                from,
+                scope,
+                key,
+                kind,
+            },
         )));
     }
 }
 
+fn record_leases(interest: &mut Interest, mid: ModuleId, ctl: &mut OrbitCtl<'_>) {
+    let leased = ctl.take_leased();
+    if !leased.is_empty() {
+        interest.entry(mid).or_default().extend(leased);
+    }
+}
+
 fn do_update(
     engine: &mut Engine,
     event: &orbit_api::Event<ErasedMsg>,
@@ -226,17 +273,17 @@ fn do_update(
 fn do_on_broadcast(
     _engine: &mut Engine,
     _event: &orbit_api::Event<ErasedMsg>,
-    (module, task, ctl, key): &mut (
+    (module, task, ctl, broadcast): &mut (
         &mut ModuleInfo,
         &mut Option<UnraveledTask>,
         &mut OrbitCtl<'_>,
-        &'static str,
+        &Broadcast,
     ),
     tid: &TargetId,
 ) -> bool {
     let (ut, redraw) = api_utils::unravel_task(
         module.toggled,
-        module.as_mut().on_broadcast(ctl, Some(*tid), key),
+        module.as_mut().on_broadcast(ctl, Some(*tid), broadcast),
     );
     **task = Some(ut);
     redraw // -> engine calls target.ctx.request_redraw() for this tid
@@ -312,17 +359,19 @@ impl ModuleManager {
             }
         }
 
-        for (_mid, handles) in self.dispatch_tokens.drain() {
-            for handle in handles {
-                self.pending_threads.push(handle.thread);
-            }
-        }
-        for (_mid, tokens) in self.sub_tokens.drain() {
+        for (_mid, tokens) in self
+            .dispatch_tokens
+            .drain()
+            .chain(self.sub_tokens.drain())
+            .chain(self.background_tokens.drain())
+        {
             for token in tokens {
                 loop_handle.remove(token);
             }
         }
-        self.reap_threads();
+        self.topics.clear();
+        self.interest.clear();
+        self.executor.cancel_all();
 
         self.modules = discover_and_load_modules(config, config_path, engine, None)?;
         Ok(())
@@ -341,7 +390,13 @@ impl ModuleManager {
             return;
         }
 
-        let usub = api_utils::unravel_sub(module.as_ref().subscriptions());
+        let (usub, _) = api_utils::unravel_sub(module.as_ref().subscriptions());
+        if !usub.topics.is_empty() {
+            self.topics
+                .entry(Scope::Shown(*mid))
+                .or_default()
+                .extend(usub.topics);
+        }
 
         let mut tokens = Vec::new();
         super::dispatch::handle_subs(usub.subs, tx, loop_handle, mid, &mut tokens);
@@ -349,16 +404,83 @@ impl ModuleManager {
             self.sub_tokens.entry(*mid).or_default().append(&mut tokens);
         }
 
-        let mut handles = Vec::new();
-        super::dispatch::handle_streams(usub.streams, tx, loop_handle, mid, &mut handles);
-        if !handles.is_empty() {
+        let mut tokens = Vec::new();
+        super::dispatch::handle_streams(
+            usub.streams,
+            tx,
+            loop_handle,
+            &mut self.executor,
+            Scope::Shown(*mid),
+            mid,
+            &mut tokens,
+        );
+        if !tokens.is_empty() {
             self.dispatch_tokens
                 .entry(*mid)
                 .or_default()
-                .append(&mut handles);
+                .append(&mut tokens);
         }
     }
 
+    /// Start the `Subscription::background` part of a loaded module. They
+    /// keep running across hide/show until
+    /// [`remove_background_subscriptions`](Self::remove_background_subscriptions).
+    pub fn add_background_subscriptions(
+        &mut self,
+        tx: &RuntimeSender,
+        loop_handle: &mut LoopHandle<SctkState>,
+        mid: &ModuleId,
+    ) {
+        let Some(module) = self.modules.get_mut(mid) else {
+            return;
+        };
+        if !module.is_loaded() || self.background_tokens.contains_key(mid) {
+            return;
+        }
+
+        let (_, usub) = api_utils::unravel_sub(module.as_ref().subscriptions());
+        if !usub.topics.is_empty() {
+            self.topics.insert(Scope::Loaded(*mid), usub.topics);
+        }
+
+        let mut tokens = Vec::new();
+        super::dispatch::handle_subs(usub.subs, tx, loop_handle, mid, &mut tokens);
+        super::dispatch::handle_streams(
+            usub.streams,
+            tx,
+            loop_handle,
+            &mut self.executor,
+            Scope::Loaded(*mid),
+            mid,
+            &mut tokens,
+        );
+        self.background_tokens.insert(*mid, tokens);
+    }
+
+    pub fn start_background_subscriptions(
+        &mut self,
+        tx: &RuntimeSender,
+        loop_handle: &mut LoopHandle<SctkState>,
+    ) {
+        for mid in self.modules.keys().copied().collect::<Vec<ModuleId>>() {
+            self.add_background_subscriptions(tx, loop_handle, &mid);
+        }
+    }
+
+    pub fn remove_background_subscriptions(
+        &mut self,
+        loop_handle: &mut LoopHandle<SctkState>,
+        mid: &ModuleId,
+    ) {
+        if let Some(tokens) = self.background_tokens.remove(mid) {
+            for token in tokens {
+                loop_handle.remove(token);
+            }
+        }
+        self.topics.remove(&Scope::Loaded(*mid));
+        self.executor.cancel(Scope::Loaded(*mid));
+    }
+
     pub fn remove_subscriptions(
         &mut self,
         loop_handle: &mut LoopHandle<SctkState>,
@@ -369,13 +491,13 @@ impl ModuleManager {
                 loop_handle.remove(token);
             }
         }
+        self.topics.remove(&Scope::Shown(*mid));
     }
 
     pub fn remove_streams(&mut self, loop_handle: &mut LoopHandle<SctkState>, mid: &ModuleId) {
         if let Some(tokens) = self.dispatch_tokens.remove(mid) {
-            for handle in tokens {
-                loop_handle.remove(handle.rx_token);
-                self.pending_threads.push(handle.thread);
+            for token in tokens {
+                loop_handle.remove(token);
             }
         }
     }
@@ -456,6 +578,7 @@ impl ModuleManager {
             module.toggled = false;
             if !module.as_ref().manifest().persistent_state {
                 module.as_mut().cleanup(engine);
+                self.interest.remove(mid);
             }
         }
 
@@ -480,7 +603,7 @@ impl ModuleManager {
 
         self.remove_subscriptions(loop_handle, mid);
         self.remove_streams(loop_handle, mid);
-        self.reap_threads();
+        self.executor.cancel(Scope::Shown(*mid));
 
         sctk.destroy_surfaces(&sids);
     }
@@ -502,9 +625,10 @@ impl ModuleManager {
             mid: &ModuleId,
             module: &mut ModuleInfo,
             tid: &TargetId,
-            pending_threads: &mut Vec<JoinHandle<()>>,
+            executor: &mut Executor,
             task: &mut Option<UnraveledTask>,
             resources: &mut ResourceManager,
+            interest: &mut Interest,
             output: Option<OutputInfo>,
         ) {
             *task = None;
@@ -516,8 +640,9 @@ impl ModuleManager {
                 &mut (module, task, &mut ctl),
                 tid,
             );
+            record_leases(interest, *mid, &mut ctl);
             let dirty = ctl.take_dirty();
-            super::dispatch::handle_task(task, mid, tx, task_tx, pending_threads);
+            super::dispatch::handle_task(task, mid, tx, task_tx, executor);
             for r in resources.take_reclaimable() {
                 r.reclaim(engine);
             }
@@ -537,9 +662,10 @@ impl ModuleManager {
                     &mid,
                     module,
                     &tid,
-                    &mut self.pending_threads,
+                    &mut self.executor,
                     &mut task,
                     &mut self.resources,
+                    &mut self.interest,
                     output_for(&self.target_output, Some(tid)),
                 );
             } else {
@@ -553,9 +679,10 @@ impl ModuleManager {
                             &mid,
                             module,
                             &tid,
-                            &mut self.pending_threads,
+                            &mut self.executor,
                             &mut task,
                             &mut self.resources,
+                            &mut self.interest,
                             output_for(&self.target_output, Some(tid)),
                         );
                     }
@@ -571,13 +698,14 @@ impl ModuleManager {
                             module.toggled,
                             module.as_mut().update(&mut ctl, None, engine, &api_event),
                         );
+                        record_leases(&mut self.interest, mid, &mut ctl);
                         task = Some(ut);
                         super::dispatch::handle_task(
                             &mut task,
                             &mid,
                             tx,
                             dispath_tx,
-                            &mut self.pending_threads,
+                            &mut self.executor,
                         );
                     }
                 };
@@ -596,9 +724,10 @@ impl ModuleManager {
                         mid,
                         module,
                         &tid,
-                        &mut self.pending_threads,
+                        &mut self.executor,
                         &mut task,
                         &mut self.resources,
+                        &mut self.interest,
                         output_for(&self.target_output, Some(tid)),
                     );
                 }
@@ -641,6 +770,7 @@ impl ModuleManager {
                 .unwrap_or(false);
             let poll_needs =
                 engine.poll(tid, &mut do_update, &mut (module, &mut task, &mut ctl), tid);
+            record_leases(&mut self.interest, *mid, &mut ctl);
             let need = configured && (poll_override || poll_needs);
             match engine.render_if_needed(
                 tid,
@@ -656,7 +786,7 @@ impl ModuleManager {
                     tracing::error!("render error: {e:?}");
                 }
             }
-            super::dispatch::handle_task(&mut task, mid, tx, task_tx, &mut self.pending_threads);
+            super::dispatch::handle_task(&mut task, mid, tx, task_tx, &mut self.executor);
         }
     }
 
@@ -696,6 +826,7 @@ impl ModuleManager {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn route_broadcast(
         &mut self,
         engine: &mut Engine,
@@ -704,12 +835,14 @@ impl ModuleManager {
         from: ModuleId,
         scope: Option<OutputTag>,
         key: &'static str,
+        kind: BroadcastKind,
     ) {
+        let reads = |mid: &ModuleId| self.interest.get(mid).is_some_and(|k| k.contains(key));
         let deliveries: Vec<(ModuleId, TargetId)> = match scope {
             None => self
                 .by_surface
                 .values()
-                .filter(|(_, mid)| *mid != from)
+                .filter(|(_, mid)| *mid != from && reads(mid))
                 .map(|(tid, mid)| (*mid, *tid))
                 .collect(),
             Some(tag) => self
@@ -717,10 +850,43 @@ impl ModuleManager {
                 .iter()
                 .filter(|(_, info)| info.tag() == tag)
                 .filter_map(|(tid, _)| self.by_target.get(tid).map(|(_, mid)| (*mid, *tid)))
+                .filter(|(mid, _)| reads(mid))
                 .collect(),
         };
+        if deliveries.is_empty() {
+            return;
+        }
+
+        let broadcast = Broadcast {
+            key,
+            kind,
+            output: scope.and_then(|tag| {
+                self.target_output
+                    .values()
+                    .find(|info| info.tag() == tag)
+                    .cloned()
+            }),
+        };
         for (mid, tid) in deliveries {
-            self.handle_broadcast(engine, tx, dispatch_tx, mid, tid, key);
+            self.handle_broadcast(engine, tx, dispatch_tx, mid, tid, &broadcast);
+        }
+    }
+
+    /// Hand a `Task::publish`ed message to every other module subscribed to
+    /// its topic, as a regular subscription message.
+    pub fn route_message(&self, tx: &RuntimeSender, from: ModuleId, message: &Published) {
+        for (scope, subs) in &self.topics {
+            let (Scope::Shown(mid) | Scope::Loaded(mid)) = *scope;
+            if mid == from {
+                continue;
+            }
+            for msg in subs.iter().filter_map(|sub| sub.deliver(message)) {
+                tx.send(crate::event::Event::Ui(crate::event::Ui::Result(
+                    crate::event::FromDispatch::Subscription,
+                    mid,
+                    msg,
+                )));
+            }
         }
     }
 
@@ -731,7 +897,7 @@ impl ModuleManager {
         dispatch_tx: &loop_channel::Sender<(ModuleId, ErasedMsg)>,
         mid: ModuleId,
         tid: TargetId,
-        key: &'static str,
+        broadcast: &Broadcast,
     ) {
         let output = output_for(&self.target_output, Some(tid));
         let Some(module) = self.modules.get_mut(&mid) else {
@@ -745,12 +911,13 @@ impl ModuleManager {
             &tid,
             &poke,
             &mut do_on_broadcast,
-            &mut (module, &mut task, &mut ctl, key),
+            &mut (module, &mut task, &mut ctl, broadcast),
             &tid,
         );
+        record_leases(&mut self.interest, mid, &mut ctl);
 
         let dirty = ctl.take_dirty();
-        super::dispatch::handle_task(&mut task, &mid, tx, dispatch_tx, &mut self.pending_threads);
+        super::dispatch::handle_task(&mut task, &mid, tx, dispatch_tx, &mut self.executor);
         for r in self.resources.take_reclaimable() {
             r.reclaim(engine);
         }
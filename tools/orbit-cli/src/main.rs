@@ -1,3 +1,5 @@
+use std::io::BufRead;
+
 use clap::{Parser, Subcommand};
 use zbus::{Result, blocking::Connection, proxy};
 
@@ -18,14 +20,16 @@ enum Commands {
         module: Option<String>,
     },
     Toggle {
-        #[arg(help = "Module name to toggle")]
+        #[arg(help = "Module name to toggle, or an instance like bar@top")]
         module: String,
     },
     Command {
-        #[arg(help = "Module name")]
+        #[arg(help = "Module name, or an instance like bar@top")]
         module: String,
         #[arg(help = "Command name")]
         command: String,
+        #[arg(help = "Arguments for the command; a lone `-` reads them line by line from stdin")]
+        args: Vec<String>,
     },
     Exit,
 }
@@ -45,6 +49,12 @@ trait Orbit {
     fn modules(&self) -> Result<String>;
     fn toggle(&self, module: &str) -> Result<()>;
     fn command(&self, module: &str, command: &str) -> Result<()>;
+    fn command_with_args(
+        &self,
+        module: &str,
+        command: &str,
+        args: &[String],
+    ) -> Result<(bool, String)>;
     fn exit(&self) -> Result<()>;
 }
 
@@ -81,9 +91,32 @@ fn main() {
                 eprintln!("Toggle failed: {e}");
             }
         }
-        Commands::Command { module, command } => {
-            if let Err(e) = proxy.command(&module, &command) {
-                eprintln!("Command failed: {e}");
+        Commands::Command {
+            module,
+            command,
+            mut args,
+        } => {
+            if args == ["-"] {
+                args = std::io::stdin()
+                    .lock()
+                    .lines()
+                    .map_while(|l| l.ok())
+                    .collect();
+            } else if args.is_empty() {
+                if let Err(e) = proxy.command(&module, &command) {
+                    eprintln!("Command failed: {e}");
+                }
+                return;
+            }
+            match proxy.command_with_args(&module, &command, &args) {
+                Ok((true, answer)) => {
+                    if !answer.is_empty() {
+                        println!("{answer}");
+                    }
+                }
+                // Nothing picked, like dmenu's exit status.
+                Ok((false, _)) => std::process::exit(1),
+                Err(e) => eprintln!("Command failed: {e}"),
             }
         }
         Commands::Exit => {
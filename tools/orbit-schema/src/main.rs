@@ -13,6 +13,7 @@ use serde_json::{Value, json};
 use orbit_common::{
     config::{Config, ConfigEvent, load_cfg},
     discovery::discover_modules,
+    loader::ModuleFile,
     watcher::ConfigWatcher,
     xdg::config_home,
 };
@@ -49,15 +50,16 @@ struct AppState {
 
 type OrbitSchemaFn = unsafe extern "C" fn() -> *const std::ffi::c_char;
 
-/// dlopen `path` and call its `orbit_schema()` export, returning the JSON
+/// dlopen `file` and call its `orbit_schema()` export, returning the JSON
 /// string it produces.
-fn load_schema_from_so(path: &std::path::Path) -> Result<Value, String> {
+fn load_schema_from_so(file: &ModuleFile) -> Result<Value, String> {
     // Safety: we are loading a trusted (permission-checked by orbit-common)
     // orbit module .so.  The `orbit_schema` symbol follows a well-known ABI:
     // it returns a pointer to a static null-terminated UTF-8 JSON string that
     // remains valid for the lifetime of the library.  We parse the JSON
     // immediately and then drop the library, so no dangling pointer can escape.
-    let lib = orbit_common::loader::LibraryHandle::open(path)?;
+    let path = file.path();
+    let lib = orbit_common::loader::LibraryHandle::open(file)?;
 
     let schema_fn: OrbitSchemaFn = unsafe { lib.get_fn(b"orbit_schema\0")? };
 
@@ -119,12 +121,16 @@ fn build_schema_map(
     let mut map = HashMap::new();
 
     for module in modules {
+        if let Some(reason) = &module.refused {
+            tracing::warn!(module = %module.name, reason = %reason, "not loading schema of refused module");
+            continue;
+        }
         if !module.enabled {
             map.insert(module.name, (false, Value::Null));
             continue;
         }
 
-        match load_schema_from_so(&module.path) {
+        match load_schema_from_so(&module.file) {
             Ok(mut schema) => {
                 if let Some(obj) = schema.as_object_mut() {
                     obj.remove("$schema");